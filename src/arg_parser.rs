@@ -2,13 +2,39 @@ use structopt::StructOpt;
 
 use std::path::PathBuf;
 
-// This function is to avoid a StructOpt import in main.rs
-pub fn parse_command() -> Command {
-    Command::from_args()
+// This function is to avoid a StructOpt import in cli.rs
+pub fn parse_args() -> Args {
+    Args::from_args()
 }
 
 #[derive(Debug, StructOpt)]
 #[structopt(name = "cbake", about = "Let me bake cmake for you")]
+pub struct Args {
+    #[structopt(
+        long = "message-format",
+        default_value = "human",
+        raw(possible_values = r#"&["human", "json"]"#, global = "true")
+    )]
+    /// Print messages for humans or as newline-delimited JSON
+    pub message_format: String,
+
+    #[structopt(long = "cmake", parse(from_os_str), raw(global = "true"))]
+    /// Path to the cmake binary, overrides the CBAKE_CMAKE environment variable
+    pub cmake: Option<PathBuf>,
+
+    #[structopt(short = "C", parse(from_os_str), raw(global = "true"))]
+    /// Run as if cbake was started in <directory>
+    pub directory: Option<PathBuf>,
+
+    #[structopt(long = "manifest-path", parse(from_os_str), raw(global = "true"))]
+    /// Path to cbake.toml or CMakeLists.txt of the project, disables the project root lookup
+    pub manifest_path: Option<PathBuf>,
+
+    #[structopt(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Debug, StructOpt)]
 pub enum Command {
     #[structopt(name = "init")]
     /// UNIMPLEMENTED!
@@ -18,25 +44,141 @@ pub enum Command {
 
     #[structopt(name = "new")]
     /// Create a project at <path>
-    New { path: PathBuf },
+    New {
+        path: PathBuf,
+
+        #[structopt(long = "lib")]
+        /// Create a library which other projects can find_package() once it is installed
+        lib: bool,
+
+        #[structopt(long = "name")]
+        /// Name of the project, the directory name with characters cmake doesn't allow replaced by default
+        name: Option<String>,
+    },
 
     #[structopt(name = "build")]
     /// Build a project
     Build {
-        #[structopt(long = "release")]
-        /// Use release configuration
-        release: bool,
+        #[structopt(flatten)]
+        args: BuildArgs,
     },
 
     #[structopt(name = "run")]
-    /// Build and run a project
+    /// Build and run a project. Foreign targets are run with the command from CBAKE_TARGET_<TRIPLE>_RUNNER
     Run {
-        #[structopt(long = "release")]
-        /// Use release configuration
-        release: bool,
+        #[structopt(flatten)]
+        args: BuildArgs,
+    },
+
+    #[structopt(name = "test")]
+    /// Build a project and run its tests with ctest
+    Test {
+        #[structopt(flatten)]
+        args: BuildArgs,
+
+        #[structopt(long = "coverage")]
+        /// Build with coverage instrumentation and write line coverage reports to build/coverage
+        coverage: bool,
+    },
+
+    #[structopt(name = "doctor")]
+    /// Check installed tools and the current project for problems
+    Doctor {},
+
+    #[structopt(name = "explain")]
+    /// Print extended help for an error code, e.g. E0007
+    Explain { code: String },
+
+    #[structopt(name = "add")]
+    /// Add source files to a project
+    Add {
+        #[structopt(subcommand)]
+        item: AddItem,
+    },
+
+    #[structopt(name = "rm")]
+    /// Remove the sources named <name> with any extension, e.g. Foo.h and Foo.cpp
+    Rm { name: String },
+
+    #[structopt(name = "watch")]
+    /// Rebuild a project when its files change, then run the program or the tests if asked
+    Watch {
+        #[structopt(default_value = "build", raw(possible_values = r#"&["build", "run", "test"]"#))]
+        /// What to do after every change
+        action: String,
+
+        #[structopt(flatten)]
+        args: BuildArgs,
+    },
+
+    #[structopt(name = "install")]
+    /// Build a project with the release profile unless --profile is given and install it
+    Install {
+        #[structopt(flatten)]
+        args: BuildArgs,
+
+        #[structopt(long = "prefix", parse(from_os_str))]
+        /// Install into <prefix>/bin instead of the cmake default, e.g. /usr/local/bin
+        prefix: Option<PathBuf>,
+    },
+
+    #[structopt(name = "package")]
+    /// Build a project with the release profile unless --profile is given and make .tar.gz and .deb packages
+    Package {
+        #[structopt(flatten)]
+        args: BuildArgs,
     },
 
     #[structopt(name = "clean")]
-    /// UNIMPLEMENTED! Clean up cmake cache
+    /// Remove the build directory
     Clean {},
 }
+
+#[derive(Debug, StructOpt)]
+pub enum AddItem {
+    #[structopt(name = "class")]
+    /// Create a header and a source file of a class
+    Class {
+        name: String,
+
+        #[structopt(long = "namespace")]
+        /// Put the class into a namespace, nested ones are separated by ::
+        namespace: Option<String>,
+
+        #[structopt(long = "pragma-once")]
+        /// Use #pragma once instead of include guards
+        pragma_once: bool,
+    },
+}
+
+// Options of the commands which build a project
+#[derive(Debug, StructOpt)]
+pub struct BuildArgs {
+    #[structopt(long = "release")]
+    /// Use release configuration, same as --profile release
+    pub release: bool,
+
+    #[structopt(long = "profile", conflicts_with = "release")]
+    /// Use a build profile: debug, release, relwithdebinfo, minsizerel or one defined in cbake.toml
+    pub profile: Option<String>,
+
+    #[structopt(long = "target")]
+    /// Cross-compile for the target triple (e.g. aarch64-linux-gnu)
+    pub target: Option<String>,
+
+    #[structopt(short = "v", long = "verbose")]
+    /// Show raw build output instead of formatted compiler diagnostics
+    pub verbose: bool,
+
+    #[structopt(long = "sanitize")]
+    /// Comma separated sanitizers to build with: address, undefined, thread, memory, leak
+    pub sanitize: Option<String>,
+
+    #[structopt(long = "warnings")]
+    /// Warning level: none, default, strict or pedantic. Overrides the one in cbake.toml
+    pub warnings: Option<String>,
+
+    #[structopt(long = "deny-warnings")]
+    /// Treat compiler warnings as errors
+    pub deny_warnings: bool,
+}
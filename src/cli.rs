@@ -0,0 +1,141 @@
+/// This module is the command line interface of the cbake binary, main.rs only calls it
+use crate::arg_parser;
+use crate::commands::{self, BuildOptions, ExecutionResult};
+use crate::error::{self, FatalError};
+use crate::manifest::ProjectKind;
+use crate::message::{self, Message, MessageFormat};
+use crate::project_layout;
+use crate::sanitizer::SanitizerSet;
+
+use std::path::{Path, PathBuf};
+
+pub fn main() {
+    let mut args = arg_parser::parse_args();
+
+    // The value is already checked by the argument parser
+    message::set_format(args.message_format.parse().unwrap_or(MessageFormat::Human));
+
+    if let Some(cmake) = args.cmake.take() {
+        crate::cmake::exec::set_cmake_program(cmake);
+    }
+
+    let result = exec_command(args);
+    commands::report_result(&result);
+
+    message::emit(Message::Finished {
+        success: result.is_ok(),
+    });
+
+    if let Err(err) = result {
+        std::process::exit(err.exit_code());
+    }
+}
+
+fn exec_command(args: arg_parser::Args) -> ExecutionResult {
+    use arg_parser::{AddItem, Command::*};
+
+    let directory = args.directory.as_deref();
+    let manifest_path = args.manifest_path.as_deref();
+    let project_dir = || project_dir(directory, manifest_path);
+
+    match args.command {
+        Init {} => commands::init(),
+        New { path, lib, name } => {
+            let kind = if lib { ProjectKind::Library } else { ProjectKind::Executable };
+            // Without -C the path is kept as given, cmake gets relative paths then
+            let path = directory.map(|dir| dir.join(&path)).unwrap_or(path);
+            commands::new(&path, kind, name.as_deref())
+        }
+        Build { args } => commands::build(&project_dir()?, &build_options(args)?),
+        Run { args } => commands::run(&project_dir()?, &build_options(args)?),
+        Test { args, coverage } => {
+            let mut options = build_options(args)?;
+            options.coverage = coverage;
+
+            commands::test(&project_dir()?, &options)
+        }
+        Add {
+            item: AddItem::Class {
+                name,
+                namespace,
+                pragma_once,
+            },
+        } => commands::add_class(&project_dir()?, &name, namespace.as_deref(), pragma_once),
+        Rm { name } => commands::rm(&project_dir()?, &name),
+        Watch { action, args } => commands::watch(&project_dir()?, &action, &build_options(args)?),
+        Install { args, prefix } => {
+            // A relative prefix is given from where cbake was started, not from the project root
+            let prefix = match prefix {
+                Some(prefix) => Some(std::env::current_dir().map_err(error::dir_access_err)?.join(prefix)),
+                None => None,
+            };
+
+            commands::install(&project_dir()?, &release_build_options(args)?, prefix.as_deref())
+        }
+        Package { args } => commands::package(&project_dir()?, &release_build_options(args)?),
+        Clean {} => commands::clean(&project_dir()?),
+        Doctor {} => commands::doctor(&project_dir()?),
+        Explain { code } => commands::explain(&code),
+    }
+}
+
+// Root of the project commands work on. The directory given with -C or the current one and its parents
+// are searched unless --manifest-path points to the project
+fn project_dir(directory: Option<&Path>, manifest_path: Option<&Path>) -> Result<PathBuf, FatalError> {
+    let current_dir = std::env::current_dir().map_err(error::dir_access_err)?;
+    let start_dir = match directory {
+        Some(dir) => current_dir.join(dir),
+        None => current_dir,
+    };
+
+    if let Some(path) = manifest_path {
+        let path = start_dir.join(path);
+
+        return Ok(match path.parent() {
+            Some(parent) if !path.is_dir() => parent.to_path_buf(),
+            _ => path,
+        });
+    }
+
+    Ok(project_layout::find_project_root(&start_dir).unwrap_or(start_dir))
+}
+
+// Installed and packaged builds are optimized unless another profile is asked for
+fn release_build_options(mut args: arg_parser::BuildArgs) -> Result<BuildOptions, FatalError> {
+    args.release = args.profile.is_none();
+    build_options(args)
+}
+
+fn build_options(args: arg_parser::BuildArgs) -> Result<BuildOptions, FatalError> {
+    // --release is a shorthand for --profile release
+    let profile = match args.profile {
+        Some(profile) => profile,
+        None if args.release => String::from("release"),
+        None => String::from("debug"),
+    };
+
+    let target = match args.target {
+        Some(triple) => Some(triple.parse().map_err(error::target_err)?),
+        None => None,
+    };
+
+    let sanitizers = match args.sanitize {
+        Some(sanitizers) => sanitizers.parse().map_err(error::sanitizer_err)?,
+        None => SanitizerSet::default(),
+    };
+
+    let warnings = match args.warnings {
+        Some(level) => Some(level.parse().map_err(error::warnings_err)?),
+        None => None,
+    };
+
+    Ok(BuildOptions {
+        profile,
+        target,
+        sanitizers,
+        coverage: false,
+        verbose: args.verbose,
+        warnings,
+        deny_warnings: args.deny_warnings,
+    })
+}
@@ -1,4 +1,45 @@
-use super::generator::{self, Generator};
+use super::generator::{self, Generator, TargetSettings};
+use super::Version;
+use crate::project_name;
+use crate::warnings::WarningPolicy;
+
+use regex::Regex;
+
+use std::sync::OnceLock;
+
+// Comment above the add_executable (add_library) command, tells CMakeLists.txt generated by cbake apart
+pub const SOURCES_SECTION_MARKER: &str =
+    "-------- Warning: This section will be overwritten by cbake utility. Don't change it manually if you will use it --------";
+
+// Compile and link settings from cbake.toml, rewritten on every build
+pub const SETTINGS_SECTION_BEGIN: &str =
+    "#-------- Warning: This section will be overwritten by cbake build. Don't change it manually --------";
+pub const SETTINGS_SECTION_END: &str = "#-------- End of the build settings section --------";
+
+// Name of the project target. Project names may contain characters CMake doesn't allow in target names,
+// so generated commands use it instead of PROJECT_NAME
+pub const TARGET_VAR: &str = "CBAKE_TARGET";
+
+// Set by cbake when it configures the project, selects the settings of the profile
+pub const PROFILE_VAR: &str = "CBAKE_PROFILE";
+
+// Sanitizer and coverage flags, set by cbake only in the build directories of such builds
+pub const COMPILE_FLAGS_VAR: &str = "CBAKE_COMPILE_FLAGS";
+pub const LINK_FLAGS_VAR: &str = "CBAKE_LINK_FLAGS";
+
+// Whether CMakeLists.txt has any of the sections cbake keeps up to date
+pub fn is_managed(cmake_lists: &str) -> bool {
+    cmake_lists.contains(SOURCES_SECTION_MARKER) || cmake_lists.contains(SETTINGS_SECTION_BEGIN)
+}
+
+// Version from cmake_minimum_required. In a generated CMakeLists.txt it is the version of the generator
+pub fn min_version(cmake_lists: &str) -> Option<Version> {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    let pattern =
+        PATTERN.get_or_init(|| Regex::new(r"(?i)cmake_minimum_required\s*\(\s*VERSION\s+([\d.]+)").unwrap());
+
+    pattern.captures(cmake_lists).and_then(|captures| captures[1].parse().ok())
+}
 
 pub struct Builder {
     generator: Box<dyn Generator>,
@@ -8,6 +49,13 @@ pub struct Builder {
     include_dirs: Vec<String>, // or PathBuf?
     default_build_type: Option<String>,
     cpp_standard: Option<String>,
+    testing: bool,
+    version: Option<String>,
+    export_namespace: Option<String>,
+    settings: TargetSettings,
+    profile_settings: Vec<(String, TargetSettings)>,
+    warnings: WarningPolicy,
+    instrumentation: bool,
 }
 
 impl Builder {
@@ -20,6 +68,13 @@ impl Builder {
             include_dirs: Vec::new(),
             default_build_type: None,
             cpp_standard: None,
+            testing: false,
+            version: None,
+            export_namespace: None,
+            settings: TargetSettings::default(),
+            profile_settings: Vec::new(),
+            warnings: WarningPolicy::default(),
+            instrumentation: false,
         }
     }
 
@@ -33,6 +88,24 @@ impl Builder {
         self
     }
 
+    // Allows tests declared with add_test() to be run by ctest
+    pub fn enable_testing(mut self) -> Self {
+        self.testing = true;
+        self
+    }
+
+    pub fn version(mut self, version: String) -> Self {
+        self.version = Some(version);
+        self
+    }
+
+    // Builds a library instead of an executable and exports it as <namespace>::<project name>.
+    // Include directories become public, so library consumers get them too
+    pub fn library(mut self, export_namespace: String) -> Self {
+        self.export_namespace = Some(export_namespace);
+        self
+    }
+
     pub fn include_dirs(mut self, mut dirs: Vec<String>) -> Self {
         self.include_dirs.append(&mut dirs);
         self
@@ -43,31 +116,227 @@ impl Builder {
         self
     }
 
+    // Definitions, compile and link options and libraries used by every profile
+    pub fn target_settings(mut self, settings: TargetSettings) -> Self {
+        self.settings = settings;
+        self
+    }
+
+    pub fn warnings(mut self, policy: WarningPolicy) -> Self {
+        self.warnings = policy;
+        self
+    }
+
+    // Applies sanitizer and coverage flags cbake passes in COMPILE_FLAGS_VAR and LINK_FLAGS_VAR
+    pub fn instrumentation(mut self) -> Self {
+        self.instrumentation = true;
+        self
+    }
+
+    // Settings used only when the project is configured with the profile
+    pub fn profile_settings(mut self, profile: String, settings: TargetSettings) -> Self {
+        if !settings.is_empty() {
+            self.profile_settings.push((profile, settings));
+        }
+        self
+    }
+
+    // Contents of the build settings section, empty if there are no settings
+    pub fn settings_section(&self) -> String {
+        let mut section = self.generator.target_settings(&self.settings);
+
+        if !self.warnings.is_empty() {
+            section += &self.generator.warnings(&self.warnings);
+        }
+
+        for (profile, settings) in &self.profile_settings {
+            let commands: String = self
+                .generator
+                .target_settings(settings)
+                .lines()
+                .map(|line| format!("    {}\n", line))
+                .collect();
+
+            section += &format!(
+                "if({} STREQUAL {})\n{}endif()\n",
+                PROFILE_VAR,
+                generator::quote(profile),
+                commands
+            );
+        }
+
+        if self.instrumentation {
+            section += &generator::target_flags_from(COMPILE_FLAGS_VAR, LINK_FLAGS_VAR);
+        }
+
+        section
+    }
+
     pub fn build(self) -> String {
         let mut cmake_lists = String::new();
 
         cmake_lists += &self.generator.min_ver();
-        cmake_lists += &generator::project(&self.project_name);
+        cmake_lists += &match &self.version {
+            Some(version) => self.generator.project_with_version(&self.project_name, version),
+            None => generator::project(&self.project_name),
+        };
 
-        if let Some(build_type) = self.default_build_type {
-            cmake_lists += &generator::default_build_type(&build_type);
+        if let Some(build_type) = &self.default_build_type {
+            cmake_lists += &generator::default_build_type(build_type);
         }
 
-        if let Some(standard) = self.cpp_standard {
-            cmake_lists += &generator::set_var("CMAKE_CXX_STANDARD", &standard);
+        if let Some(standard) = &self.cpp_standard {
+            cmake_lists += &generator::set_var("CMAKE_CXX_STANDARD", standard);
             cmake_lists.push('\n');
         }
 
-        if !self.include_dirs.is_empty() {
+        if self.testing {
+            cmake_lists += &generator::enable_testing();
+        }
+
+        let is_library = self.export_namespace.is_some();
+
+        if !is_library && !self.include_dirs.is_empty() {
             cmake_lists += &generator::include_dirs(&self.include_dirs);
         }
 
         if !self.sources.is_empty() {
-            cmake_lists += &generator::comment("-------- Warning: This section will be overwritten by cbake utility. Don't change it manually if you will use it --------");
-            cmake_lists += &generator::add_executable(&self.sources);
+            cmake_lists += &generator::comment(SOURCES_SECTION_MARKER);
+            cmake_lists += &generator::set_var(TARGET_VAR, &project_name::target_name(&self.project_name));
+            cmake_lists += &if is_library {
+                generator::add_library(&self.sources)
+            } else {
+                generator::add_executable(&self.sources)
+            };
             cmake_lists += &generator::comment("--------------------------------------------------------------------------------------------------------------------------");
         }
 
+        let settings = self.settings_section();
+        if !settings.is_empty() {
+            cmake_lists += &format!("\n{}\n{}{}\n", SETTINGS_SECTION_BEGIN, settings, SETTINGS_SECTION_END);
+        }
+
+        if let Some(namespace) = &self.export_namespace {
+            if !self.include_dirs.is_empty() {
+                cmake_lists.push('\n');
+                cmake_lists += &self.generator.public_include_dirs(&self.include_dirs);
+            }
+
+            cmake_lists += &generator::comment(&format!(
+                "-------- Package config: lets other projects find_package({}) the installed library --------",
+                project_name::target_name(&self.project_name)
+            ));
+            cmake_lists += &self.generator.export_package(namespace, &self.include_dirs);
+        }
+
         cmake_lists
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::super::snapshot;
+    use super::*;
+    use crate::warnings::WarningLevel;
+
+    fn sources() -> Vec<String> {
+        vec![String::from("src/main.cpp"), String::from("src/util.cpp")]
+    }
+
+    #[test]
+    fn snapshots() {
+        let mut snapshots = Vec::new();
+
+        for (name, _) in snapshot::generators() {
+            // Each case gets a fresh generator, builders take ownership of it
+            let generator = || snapshot::generators().into_iter().find(|(n, _)| *n == name).unwrap().1;
+            let builder = || Builder::new(String::from("demo"), generator());
+
+            let minimal = builder().sources(sources()).build();
+
+            // The way cbake new sets up an executable
+            let executable = builder()
+                .version(String::from("0.1.0"))
+                .default_build_type(String::from("Debug"))
+                .cpp_standard(11)
+                .enable_testing()
+                .include_dirs(vec![String::from("include")])
+                .sources(sources())
+                .build();
+
+            let library = builder()
+                .version(String::from("1.2.0"))
+                .include_dirs(vec![String::from("include")])
+                .library(String::from("demo"))
+                .sources(vec![String::from("demo.cpp")])
+                .build();
+
+            let settings = builder()
+                .sources(sources())
+                .target_settings(TargetSettings {
+                    definitions: vec![String::from("USE_FOO"), String::from("LEVEL=2")],
+                    compile_options: vec![String::from("-fno-exceptions")],
+                    link_options: vec![String::from("-Wl,--as-needed")],
+                    link_libraries: vec![String::from("pthread")],
+                })
+                .profile_settings(
+                    String::from("release"),
+                    TargetSettings {
+                        compile_options: vec![String::from("-march=native")],
+                        link_options: vec![String::from("-s")],
+                        ..TargetSettings::default()
+                    },
+                )
+                .profile_settings(String::from("debug"), TargetSettings::default())
+                .warnings(WarningPolicy {
+                    level: WarningLevel::Strict,
+                    deny: true,
+                })
+                .instrumentation()
+                .build();
+
+            // File names which need quoting and escaping
+            let paths = builder()
+                .include_dirs(vec![String::from("include"), String::from("third party/include")])
+                .library(String::from("demo"))
+                .sources(vec![
+                    String::from("my file.cpp"),
+                    String::from("a;b.cpp"),
+                    String::from("${x}.cpp"),
+                ])
+                .build();
+
+            // The project keeps its name, the target gets one CMake accepts
+            let unicode = Builder::new(String::from("café"), generator())
+                .version(String::from("0.1.0"))
+                .sources(sources())
+                .build();
+
+            for (case, cmake_lists) in [
+                ("minimal", minimal),
+                ("executable", executable),
+                ("library", library),
+                ("settings", settings),
+                ("paths", paths),
+                ("unicode", unicode),
+            ] {
+                snapshots.push((format!("{}_builder_{}", name, case), cmake_lists));
+            }
+        }
+
+        snapshot::assert_snapshots(&snapshots);
+    }
+
+    #[test]
+    fn min_version() {
+        for (_, generator) in snapshot::generators() {
+            let cmake_lists = Builder::new(String::from("demo"), generator).sources(sources()).build();
+            let version = super::min_version(&cmake_lists).unwrap();
+
+            assert_eq!(generator::from_version(version).min_ver(), generator::min_ver(&version));
+        }
+
+        assert_eq!(super::min_version("CMAKE_MINIMUM_REQUIRED( VERSION 3.10)"), Some(Version::new(3, 10)));
+        assert_eq!(super::min_version("project(demo)\n"), None);
+    }
+}
@@ -4,76 +4,317 @@ use super::version::Version;
 use regex::Regex;
 
 use std::{
+    env,
     error::Error,
     ffi::OsString,
-    fmt, io,
-    path::PathBuf,
-    process::{Command, Stdio},
+    fmt,
+    io::{self, BufRead, BufReader, Read},
+    path::{Path, PathBuf},
+    process::{Command, ExitStatus, Stdio},
+    sync::{mpsc, OnceLock},
+    thread,
 };
 
-pub fn version() -> Result<Version, Box<dyn std::error::Error>> {
-    let exec_status = Command::new("cmake").arg("--version").output()?;
+// Environment variable with a path to the cmake binary. The --cmake option takes precedence
+pub const CMAKE_ENV_VAR: &str = "CBAKE_CMAKE";
 
-    let command_output = String::from_utf8(exec_status.stdout)?;
+// The binary is chosen once on startup, so it is kept globally instead of being passed everywhere
+static CMAKE_PROGRAM: OnceLock<PathBuf> = OnceLock::new();
 
-    // Pattern to search version numbers like 1.2 or 1.2.3
-    let ver_pattern = Regex::new(r"(\d+\.){1,2}(\d+)").unwrap();
+pub fn set_cmake_program(program: PathBuf) {
+    let _ = CMAKE_PROGRAM.set(program);
+}
 
-    let version: Version = ver_pattern
-        .find_iter(&command_output)
-        .next() // Get first match
-        .unwrap() // If command executed without errors the version string must be in the command output
-        .as_str() // Convert regex::Match to str
-        .parse()?; // Convert to Version type
+pub fn cmake_program() -> PathBuf {
+    if let Some(program) = CMAKE_PROGRAM.get() {
+        return program.clone();
+    }
 
-    Ok(version)
+    match env::var_os(CMAKE_ENV_VAR) {
+        Some(program) if !program.is_empty() => PathBuf::from(program),
+        _ => PathBuf::from("cmake"),
+    }
 }
 
-pub fn init(project_dir: &PathBuf, build_dir: &PathBuf) -> Result<(), ExecutionError> {
-    let mut arg1 = OsString::from("-B");
-    let mut arg2 = OsString::from("-H");
+// ctest and cpack are shipped along with cmake, e.g. /opt/cmake/bin/cmake comes with
+// /opt/cmake/bin/ctest and cmake3 with ctest3
+fn cmake_tool(tool: &str) -> PathBuf {
+    let cmake = cmake_program();
 
-    arg1.push(build_dir.as_os_str());
-    arg2.push(project_dir.as_os_str());
+    let tool_name = match cmake.file_name().and_then(|name| name.to_str()) {
+        Some(name) if name.contains("cmake") => name.replacen("cmake", tool, 1),
+        _ => return PathBuf::from(tool),
+    };
 
-    let output = Command::new("cmake")
-        .arg(arg1)
-        .arg(arg2)
-        .stdout(Stdio::inherit())
-        .output()?;
+    let tool_path = cmake.with_file_name(tool_name);
 
-    if output.status.success() {
-        Ok(())
+    if tool_path.components().count() == 1 || tool_path.exists() {
+        tool_path
     } else {
-        let exit_code = output.status.code().unwrap_or(-1);
-        Err(ExecutionError::from(CMakeFailure(exit_code)))
+        PathBuf::from(tool)
+    }
+}
+
+pub fn ctest_program() -> PathBuf {
+    cmake_tool("ctest")
+}
+
+pub fn cpack_program() -> PathBuf {
+    cmake_tool("cpack")
+}
+
+pub fn version() -> Result<Version, VersionError> {
+    let output = Command::new(cmake_program()).arg("--version").output()?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let message = stderr.lines().map(str::trim).find(|line| !line.is_empty()).unwrap_or_default();
+
+        return Err(VersionError::Status(output.status.code().unwrap_or(-1), message.to_string()));
     }
+
+    parse_version_output(&String::from_utf8_lossy(&output.stdout))
 }
 
-pub fn build(build_dir: &PathBuf) -> Result<(), ExecutionError> {
-    let output = Command::new("cmake")
+// Finds the version in the `cmake --version` output like "cmake version 3.16.3" or
+// "cmake3 version 3.28.0-rc2". Any version-like word is accepted if the usual line is missing
+pub fn parse_version_output(output: &str) -> Result<Version, VersionError> {
+    let version_line = Regex::new(r"(?m)^\S*\s+version\s+(\d+\.\d+\S*)").unwrap();
+    let version_word = Regex::new(r"\b(\d+\.\d+\S*)").unwrap();
+
+    let version = version_line
+        .captures(output)
+        .or_else(|| version_word.captures(output))
+        .map(|captures| captures[1].to_string())
+        .ok_or_else(|| VersionError::NoVersion(output.trim().to_string()))?;
+
+    version.parse().map_err(|_| VersionError::NoVersion(version))
+}
+
+// Output of a build. Lines of stdout and stderr are kept in the order they arrived
+pub struct BuildOutput {
+    pub success: bool,
+    pub lines: Vec<String>,
+}
+
+// Builds the project capturing its output. Every line is also passed to on_line as it arrives
+pub fn build(build_dir: &Path, on_line: &mut dyn FnMut(&str)) -> io::Result<BuildOutput> {
+    let mut child = Command::new(cmake_program())
         .arg("--build")
         .arg(build_dir.as_os_str())
-        .stdout(Stdio::inherit())
-        .output()?;
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    let (sender, receiver) = mpsc::channel();
+
+    let readers: Vec<Box<dyn Read + Send>> = vec![
+        Box::new(child.stdout.take().expect("stdout is piped")),
+        Box::new(child.stderr.take().expect("stderr is piped")),
+    ];
+
+    let reader_threads: Vec<_> = readers
+        .into_iter()
+        .map(|reader| {
+            let sender = sender.clone();
+            thread::spawn(move || {
+                for line in BufReader::new(reader).lines().map_while(Result::ok) {
+                    if sender.send(line).is_err() {
+                        break;
+                    }
+                }
+            })
+        })
+        .collect();
+
+    // The channel is closed once both readers are done
+    drop(sender);
+
+    let mut lines = Vec::new();
+    for line in receiver {
+        on_line(&line);
+        lines.push(line);
+    }
+
+    for reader_thread in reader_threads {
+        let _ = reader_thread.join();
+    }
 
-    if output.status.success() {
+    let status = child.wait()?;
+
+    Ok(BuildOutput {
+        success: status.success(),
+        lines,
+    })
+}
+
+// Output of a ctest run
+pub struct TestOutput {
+    pub success: bool,
+    pub lines: Vec<String>,
+}
+
+// Result of a single test as reported by ctest
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestResult {
+    pub name: String,
+    pub success: bool,
+    pub duration: Option<f64>,
+}
+
+// Runs ctest in the build directory. Every line is also passed to on_line as it arrives
+pub fn test(
+    build_dir: &Path,
+    config: &str,
+    envs: &[(&str, &str)],
+    on_line: &mut dyn FnMut(&str),
+) -> io::Result<TestOutput> {
+    let mut command = Command::new(ctest_program());
+    command
+        .arg("--output-on-failure")
+        .arg("-C")
+        .arg(config)
+        .envs(envs.iter().cloned())
+        .current_dir(build_dir);
+
+    let (status, lines) = run_with_output(command, on_line)?;
+
+    Ok(TestOutput {
+        success: status.success(),
+        lines,
+    })
+}
+
+// Runs the command passing every line of its stdout to on_line as it arrives
+fn run_with_output(mut command: Command, on_line: &mut dyn FnMut(&str)) -> io::Result<(ExitStatus, Vec<String>)> {
+    let mut child = command.stdout(Stdio::piped()).spawn()?;
+
+    let mut lines = Vec::new();
+
+    if let Some(stdout) = child.stdout.take() {
+        for line in BufReader::new(stdout).lines() {
+            let line = line?;
+            on_line(&line);
+            lines.push(line);
+        }
+    }
+
+    let status = child.wait()?;
+
+    Ok((status, lines))
+}
+
+// Installs a built project. `cmake --install` appeared in cmake 3.15, older versions run
+// the install script generated in the build directory
+pub fn install(
+    build_dir: &Path,
+    config: &str,
+    prefix: Option<&Path>,
+    cmake_version: Version,
+    on_line: &mut dyn FnMut(&str),
+) -> Result<(), ExecutionError> {
+    let mut command = Command::new(cmake_program());
+
+    if cmake_version >= Version::new(3, 15) {
+        command.arg("--install").arg(build_dir).arg("--config").arg(config);
+
+        if let Some(prefix) = prefix {
+            command.arg("--prefix").arg(prefix);
+        }
+    } else {
+        command.arg(format!("-DBUILD_TYPE={}", config));
+
+        if let Some(prefix) = prefix {
+            let mut prefix_var = OsString::from("-DCMAKE_INSTALL_PREFIX=");
+            prefix_var.push(prefix);
+            command.arg(prefix_var);
+        }
+
+        command.arg("-P").arg(build_dir.join("cmake_install.cmake"));
+    }
+
+    let (status, _) = run_with_output(command, on_line)?;
+
+    if status.success() {
         Ok(())
     } else {
-        let exit_code = output.status.code().unwrap_or(-1);
-        Err(ExecutionError::from(CMakeFailure(exit_code)))
+        Err(ExecutionError::from(CMakeFailure(status.code().unwrap_or(-1))))
     }
 }
 
+pub struct PackageOutput {
+    pub success: bool,
+    pub packages: Vec<PathBuf>,
+}
+
+// Makes packages of a built project with cpack, generators are set in CMakeLists.txt
+pub fn package(build_dir: &Path, config: &str, on_line: &mut dyn FnMut(&str)) -> io::Result<PackageOutput> {
+    let mut command = Command::new(cpack_program());
+    command.arg("-C").arg(config).current_dir(build_dir);
+
+    let (status, lines) = run_with_output(command, on_line)?;
+
+    Ok(PackageOutput {
+        success: status.success(),
+        packages: lines.iter().filter_map(|line| parse_package_line(line)).collect(),
+    })
+}
+
+// Parses cpack lines like:
+// CPack: - package: /home/user/demo/build/demo-0.1.0-Linux-x86_64.deb generated.
+pub fn parse_package_line(line: &str) -> Option<PathBuf> {
+    let package = line.trim().strip_prefix("CPack: - package: ")?.strip_suffix(" generated.")?;
+
+    Some(PathBuf::from(package))
+}
+
+// Parses ctest progress lines like:
+// 1/2 Test #1: unit_tests .......................   Passed    0.01 sec
+// 2/2 Test #2: integration .....................***Failed    0.02 sec
+pub fn parse_test_result(line: &str) -> Option<TestResult> {
+    // Called for every line of ctest output, so the pattern is compiled once
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    let pattern = PATTERN.get_or_init(|| {
+        Regex::new(
+            r"^\s*\d+/\d+\s+Test\s+#\d+:\s+(?P<name>\S+)\s+\.*\s*(?P<result>\*\*\*)?(?P<status>[A-Za-z][A-Za-z ]*?)\s+(?P<duration>[\d.]+)\s+sec",
+        )
+        .unwrap()
+    });
+
+    let captures = pattern.captures(line)?;
+
+    Some(TestResult {
+        name: captures["name"].to_string(),
+        success: captures.name("result").is_none() && &captures["status"] == "Passed",
+        duration: captures["duration"].parse().ok(),
+    })
+}
+
 pub struct InitExtBuilder {
     variables: Vec<String>,
+    output_to_stderr: bool,
+}
+
+impl Default for InitExtBuilder {
+    fn default() -> Self {
+        InitExtBuilder::new()
+    }
 }
 
 impl InitExtBuilder {
+    // compile_commands.json is always exported for editors and tools like clangd
     pub fn new() -> Self {
         InitExtBuilder {
             variables: Vec::new(),
+            output_to_stderr: false,
         }
+        .set_var("CMAKE_EXPORT_COMPILE_COMMANDS", "ON")
+    }
+
+    // Keeps stdout clean for machine-readable output of cbake itself
+    pub fn output_to_stderr(mut self, redirect: bool) -> Self {
+        self.output_to_stderr = redirect;
+        self
     }
 
     pub fn set_var(mut self, name: &str, value: &str) -> Self {
@@ -81,7 +322,7 @@ impl InitExtBuilder {
         self
     }
 
-    pub fn execute(self, source_dir: &PathBuf, build_dir: &PathBuf) -> Result<(), ExecutionError> {
+    pub fn execute(self, source_dir: &Path, build_dir: &Path) -> Result<(), ExecutionError> {
         let optional_args = self.variables;
         let mut arg1 = OsString::from("-B");
         let mut arg2 = OsString::from("-H");
@@ -89,17 +330,23 @@ impl InitExtBuilder {
         arg1.push(build_dir.as_os_str());
         arg2.push(source_dir.as_os_str());
 
-        let output = Command::new("cmake")
+        let stdout = if self.output_to_stderr {
+            Stdio::from(io::stderr())
+        } else {
+            Stdio::inherit()
+        };
+
+        let status = Command::new(cmake_program())
             .args(optional_args)
             .arg(arg1)
             .arg(arg2)
-            .stdout(Stdio::inherit())
-            .output()?;
+            .stdout(stdout)
+            .status()?;
 
-        if output.status.success() {
+        if status.success() {
             Ok(())
         } else {
-            let exit_code = output.status.code().unwrap_or(-1);
+            let exit_code = status.code().unwrap_or(-1);
             Err(ExecutionError::from(CMakeFailure(exit_code)))
         }
     }
@@ -116,7 +363,7 @@ impl Error for CMakeFailure {
 
 impl fmt::Display for CMakeFailure {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", format!("cmake failed with exit code: {}", self.0))
+        write!(f, "cmake failed with exit code: {}", self.0)
     }
 }
 
@@ -141,6 +388,41 @@ impl fmt::Display for ExecutionError {
     }
 }
 
+#[derive(Debug)]
+pub enum VersionError {
+    IO(io::Error),
+    Status(i32, String),
+    NoVersion(String),
+}
+
+impl Error for VersionError {
+    fn description(&self) -> &str {
+        "Cannot obtain a cmake version"
+    }
+}
+
+impl fmt::Display for VersionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            VersionError::IO(err) => write!(f, "{}", err),
+            VersionError::Status(code, message) if message.is_empty() => {
+                write!(f, "cmake --version failed with exit code: {}", code)
+            }
+            VersionError::Status(code, message) => {
+                write!(f, "cmake --version failed with exit code {}: {}", code, message)
+            }
+            VersionError::NoVersion(output) if output.is_empty() => write!(f, "cmake --version printed nothing"),
+            VersionError::NoVersion(output) => write!(f, "unexpected cmake --version output `{}`", output),
+        }
+    }
+}
+
+impl From<io::Error> for VersionError {
+    fn from(err: io::Error) -> Self {
+        VersionError::IO(err)
+    }
+}
+
 impl From<io::Error> for ExecutionError {
     fn from(err: io::Error) -> Self {
         ExecutionError::IO(err)
@@ -152,3 +434,57 @@ impl From<CMakeFailure> for ExecutionError {
         ExecutionError::CMake(err)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_results() {
+        let passed = parse_test_result("1/2 Test #1: unit_tests .......................   Passed    0.01 sec");
+        assert_eq!(
+            passed,
+            Some(TestResult {
+                name: String::from("unit_tests"),
+                success: true,
+                duration: Some(0.01),
+            })
+        );
+
+        let failed = parse_test_result("2/2 Test #2: integration .....................***Failed    1.25 sec");
+        assert_eq!(failed.as_ref().map(|result| result.success), Some(false));
+        assert_eq!(failed.and_then(|result| result.duration), Some(1.25));
+
+        let not_run = parse_test_result("    Start 1: unit_tests");
+        assert_eq!(not_run, None);
+        assert_eq!(parse_test_result("100% tests passed, 0 tests failed out of 2"), None);
+    }
+
+    #[test]
+    fn package_lines() {
+        assert_eq!(
+            parse_package_line("CPack: - package: /demo/build/demo-0.1.0-Linux-x86_64.deb generated."),
+            Some(PathBuf::from("/demo/build/demo-0.1.0-Linux-x86_64.deb"))
+        );
+        assert_eq!(parse_package_line("CPack: Create package using DEB"), None);
+    }
+
+    #[test]
+    fn version_output() {
+        let outputs = [
+            ("cmake version 3.16.3\n\nCMake suite maintained and supported by Kitware (kitware.com/cmake).\n", "3.16.3"),
+            ("cmake3 version 3.28.0-rc2\n", "3.28.0-rc2"),
+            ("cmake version 3.22.1-msvc1\n", "3.22.1"),
+            ("cmake version 2.8.12.2\n", "2.8.12"),
+            ("cmake version 3.29.20240311-gabc1234\n", "3.29.20240311"),
+            ("3.18.4\n", "3.18.4"),
+        ];
+
+        for (output, version) in outputs.iter() {
+            assert_eq!(parse_version_output(output).unwrap().to_string(), *version);
+        }
+
+        assert!(parse_version_output("").is_err());
+        assert!(parse_version_output("cmake: command not found").is_err());
+    }
+}
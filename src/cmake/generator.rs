@@ -1,4 +1,5 @@
 use super::version::Version;
+use crate::warnings::WarningPolicy;
 
 pub fn from_version(ver: Version) -> Box<dyn Generator> {
     if ver < Version::new(3, 0) {
@@ -11,10 +12,7 @@ pub fn from_version(ver: Version) -> Box<dyn Generator> {
 // Version independent commands
 
 pub fn min_ver(version: &Version) -> String {
-    format!(
-        "cmake_minimum_required(VERSION {})\n\n",
-        version.to_string()
-    )
+    format!("cmake_minimum_required(VERSION {})\n\n", version)
 }
 
 pub fn set_var(var_name: &str, var_val: &str) -> String {
@@ -29,31 +27,158 @@ pub fn comment(s: &str) -> String {
     format!("#{}\n", s)
 }
 
+pub fn enable_testing() -> String {
+    String::from("enable_testing()\n\n")
+}
+
+// Build type used when none is given. Multi-config generators like Visual Studio ignore
+// CMAKE_BUILD_TYPE, so it's only set for single-config ones
 pub fn default_build_type(build_type: &str) -> String {
     format!(
-        r#"
-if(NOT CMAKE_BUILD_TYPE) 
-set(CMAKE_BUILD_TYPE, "{}")
-endif()
-
-"#,
-        build_type
+        "if(NOT CMAKE_BUILD_TYPE AND NOT CMAKE_CONFIGURATION_TYPES)\n    \
+         set(CMAKE_BUILD_TYPE {} CACHE STRING \"Choose the type of build\" FORCE)\n    \
+         set_property(CACHE CMAKE_BUILD_TYPE PROPERTY STRINGS Debug Release RelWithDebInfo MinSizeRel)\n\
+         endif()\n\n",
+        quote(build_type)
     )
 }
 
 pub fn include_dirs(dir_names: &[String]) -> String {
-    let dir_list = dir_names.join("\n");
-    format!("include_directories(\n    {}\n)\n\n", dir_list)
+    format!("include_directories(\n    {}\n)\n\n", path_lines(dir_names))
 }
 
 pub fn add_executable(sources: &[String]) -> String {
-    let source_list = sources.join("\n    ");
     format!(
-        "\nadd_executable(${{PROJECT_NAME}}\n    {}\n)\n\n",
-        source_list
+        "\nadd_executable(${{CBAKE_TARGET}}\n    {}\n)\n\n",
+        path_lines(sources)
+    )
+}
+
+pub fn add_library(sources: &[String]) -> String {
+    format!("\nadd_library(${{CBAKE_TARGET}}\n    {}\n)\n\n", path_lines(sources))
+}
+
+// Appends the flags held by the variables to the compile and link flags of the project target.
+// Flags are strings, the COMPILE_FLAGS and LINK_FLAGS properties work the same in every cmake version
+pub fn target_flags_from(compile_var: &str, link_var: &str) -> String {
+    format!(
+        "if({0})\n    \
+         set_property(TARGET ${{CBAKE_TARGET}} APPEND_STRING PROPERTY COMPILE_FLAGS \" ${{{0}}}\")\n\
+         endif()\n\
+         if({1})\n    \
+         set_property(TARGET ${{CBAKE_TARGET}} APPEND_STRING PROPERTY LINK_FLAGS \" ${{{1}}}\")\n\
+         endif()\n",
+        compile_var, link_var
     )
 }
 
+// Quoted argument taken literally, so values with spaces or quotes stay a single argument and `$` isn't expanded
+pub fn quote(value: &str) -> String {
+    format!("\"{}\"", escape_text(value))
+}
+
+// Text which can be put inside a quoted argument
+fn escape_quoted(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+// Literal text, like package metadata, where `${...}` must not be expanded
+fn escape_text(value: &str) -> String {
+    escape_quoted(value).replace('$', "\\$")
+}
+
+// Values are quoted only when cmake would split them or take them for syntax. Variable references
+// are kept, options may use them on purpose
+fn argument(value: &str) -> String {
+    if value.is_empty() || value.contains(|c: char| c.is_whitespace() || "\"\\();#".contains(c)) {
+        format!("\"{}\"", escape_quoted(value))
+    } else {
+        value.to_string()
+    }
+}
+
+fn arguments(values: &[String]) -> String {
+    values.iter().map(|value| argument(value)).collect::<Vec<_>>().join(" ")
+}
+
+// File or directory name as a single argument. Unlike compile options, which may refer to variables
+// on purpose, paths are taken literally, so variable references and list separators are escaped too
+pub fn path(value: &str) -> String {
+    if value.is_empty() || value.contains(|c: char| c.is_whitespace() || "\"\\();#$".contains(c)) {
+        format!("\"{}\"", escape_path(value))
+    } else {
+        value.to_string()
+    }
+}
+
+fn escape_path(value: &str) -> String {
+    escape_text(value).replace(';', "\\;")
+}
+
+// One path per line, for lists of sources and directories
+fn path_lines(values: &[String]) -> String {
+    values.iter().map(|value| path(value)).collect::<Vec<_>>().join("\n    ")
+}
+
+// Directory of the project inside $<BUILD_INTERFACE:...>, where `>`, `,` and `;` are written as
+// generator expressions of their own
+fn build_interface_path(dir: &str) -> String {
+    let escaped = escape_quoted(dir)
+        .replace('$', "\\$")
+        .replace('>', "$<ANGLE-R>")
+        .replace(',', "$<COMMA>")
+        .replace(';', "$<SEMICOLON>");
+    let genex = format!("$<BUILD_INTERFACE:${{CMAKE_CURRENT_SOURCE_DIR}}/{}>", escaped);
+
+    if escaped == dir && path(dir) == dir {
+        genex
+    } else {
+        format!("\"{}\"", genex)
+    }
+}
+
+// Length of the command at the start of `text` up to and including its closing parenthesis.
+// Parentheses inside quoted arguments like "f(x).cpp" don't close the command
+pub fn command_len(text: &str) -> Option<usize> {
+    let mut quoted = false;
+    let mut escaped = false;
+
+    for (i, c) in text.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => quoted = !quoted,
+            ')' if !quoted => return Some(i + 1),
+            _ => {}
+        }
+    }
+
+    None
+}
+
+// CPack settings for .tar.gz and .deb packages of the project
+pub fn cpack(name: &str, version: &str, maintainer: &str, description: Option<&str>) -> String {
+    let mut cpack = String::new();
+
+    cpack += &set_var("CPACK_PACKAGE_NAME", &quote(name));
+    cpack += &set_var("CPACK_PACKAGE_VERSION", &quote(version));
+    cpack += &set_var("CPACK_PACKAGE_CONTACT", &quote(maintainer));
+    cpack += &set_var("CPACK_DEBIAN_PACKAGE_MAINTAINER", &quote(maintainer));
+
+    if let Some(description) = description {
+        cpack += &set_var("CPACK_PACKAGE_DESCRIPTION_SUMMARY", &quote(description));
+    }
+
+    cpack += &set_var(
+        "CPACK_PACKAGE_FILE_NAME",
+        "\"${CPACK_PACKAGE_NAME}-${CPACK_PACKAGE_VERSION}-${CMAKE_SYSTEM_NAME}-${CMAKE_SYSTEM_PROCESSOR}\"",
+    );
+    cpack += &set_var("CPACK_GENERATOR", "\"TGZ;DEB\"");
+    cpack += "include(CPack)\n";
+
+    cpack
+}
+
 pub trait Generator {
     fn version(&self) -> Version;
 
@@ -61,21 +186,691 @@ pub trait Generator {
         min_ver(&self.version())
     }
 
-    // TODO: Add version dependent commands
+    // Installs the executable and, if the project has one, the public include directory
+    fn install_rules(&self, include_dir: Option<&str>) -> String;
+
+    fn project_with_version(&self, name: &str, version: &str) -> String;
+
+    // Include directories of the library which are also used by its consumers
+    fn public_include_dirs(&self, dir_names: &[String]) -> String;
+
+    // Installs the library with <Name>Config.cmake and <Name>ConfigVersion.cmake, so that other
+    // projects can find_package() it and link to the <namespace>::<Name> target
+    fn export_package(&self, namespace: &str, include_dirs: &[String]) -> String;
+
+    // Writes <Name>.pc and installs it into lib/pkgconfig. The prefix in the file is relative
+    // to the file itself, so it's correct whatever prefix the library is installed to
+    fn pkg_config(&self, package: &PkgConfig) -> String;
+
+    fn target_settings(&self, settings: &TargetSettings) -> String;
+
+    // Warning flags of the compiler the project is built with
+    fn warnings(&self, policy: &WarningPolicy) -> String;
+}
+
+// Compile and link settings of the project target
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TargetSettings {
+    pub definitions: Vec<String>,
+    pub compile_options: Vec<String>,
+    pub link_options: Vec<String>,
+    pub link_libraries: Vec<String>,
 }
 
+impl TargetSettings {
+    pub fn is_empty(&self) -> bool {
+        self.definitions.is_empty()
+            && self.compile_options.is_empty()
+            && self.link_options.is_empty()
+            && self.link_libraries.is_empty()
+    }
+}
+
+// Contents of a pkg-config file
+pub struct PkgConfig<'a> {
+    pub name: &'a str,
+    pub version: &'a str,
+    pub description: Option<&'a str>,
+    // Libraries the consumers have to link in addition to this one, like pthread or -lm
+    pub link_libs: &'a [String],
+}
+
+#[allow(non_camel_case_types)]
 pub struct CMake_2_8;
 
 impl Generator for CMake_2_8 {
     fn version(&self) -> Version {
         Version::new(2, 8)
     }
+
+    fn install_rules(&self, include_dir: Option<&str>) -> String {
+        let mut rules = String::from("install(TARGETS ${CBAKE_TARGET} RUNTIME DESTINATION bin)\n");
+
+        if let Some(include_dir) = include_dir {
+            rules += &format!("install(DIRECTORY {} DESTINATION include)\n", path(&format!("{}/", include_dir)));
+        }
+
+        rules
+    }
+
+    // project() has no VERSION option before cmake 3.0
+    fn project_with_version(&self, name: &str, version: &str) -> String {
+        format!("project({})\nset(PROJECT_VERSION {})\n\n", name, version)
+    }
+
+    fn public_include_dirs(&self, dir_names: &[String]) -> String {
+        include_dirs(dir_names)
+    }
+
+    // Targets don't carry include directories before cmake 2.8.12, so the config file
+    // provides them in <Name>_INCLUDE_DIRS
+    fn export_package(&self, namespace: &str, include_dirs: &[String]) -> String {
+        let mut export = String::from("include(CMakePackageConfigHelpers)\n\n");
+
+        export += &format!(
+            "install(TARGETS ${{CBAKE_TARGET}} EXPORT ${{CBAKE_TARGET}}Targets\n    \
+             ARCHIVE DESTINATION lib\n    \
+             LIBRARY DESTINATION lib\n    \
+             RUNTIME DESTINATION bin\n)\n\
+             {}\
+             install(EXPORT ${{CBAKE_TARGET}}Targets\n    \
+             NAMESPACE {}::\n    \
+             DESTINATION lib/cmake/${{CBAKE_TARGET}}\n)\n\n",
+            install_include_dirs(include_dirs, "include"),
+            namespace
+        );
+
+        export += &config_file(r#"set(${CBAKE_TARGET}_INCLUDE_DIRS \"\${CMAKE_CURRENT_LIST_DIR}/../../../include\")\n"#);
+        export += &config_version_file("lib/cmake/${CBAKE_TARGET}");
+
+        export
+    }
+
+    fn pkg_config(&self, package: &PkgConfig) -> String {
+        let mut pkg_config = pc_file(package, "../..", "lib", "include");
+        pkg_config += "install(FILES ${CMAKE_CURRENT_BINARY_DIR}/${CBAKE_TARGET}.pc DESTINATION lib/pkgconfig)\n";
+
+        pkg_config
+    }
+
+    // There are no target_compile_* commands, definitions and compile options apply to the whole directory
+    fn target_settings(&self, settings: &TargetSettings) -> String {
+        let mut commands = String::new();
+
+        if !settings.definitions.is_empty() {
+            let definitions: Vec<String> = settings.definitions.iter().map(|def| format!("-D{}", def)).collect();
+            commands += &format!("add_definitions({})\n", arguments(&definitions));
+        }
+
+        if !settings.compile_options.is_empty() {
+            let options = escape_quoted(&settings.compile_options.join(" "));
+            commands += &format!("set(CMAKE_C_FLAGS \"${{CMAKE_C_FLAGS}} {}\")\n", options);
+            commands += &format!("set(CMAKE_CXX_FLAGS \"${{CMAKE_CXX_FLAGS}} {}\")\n", options);
+        }
+
+        if !settings.link_options.is_empty() {
+            commands += &link_flags_property(&settings.link_options);
+        }
+
+        if !settings.link_libraries.is_empty() {
+            commands += &format!(
+                "target_link_libraries(${{CBAKE_TARGET}} {})\n",
+                arguments(&settings.link_libraries)
+            );
+        }
+
+        commands
+    }
+
+    fn warnings(&self, policy: &WarningPolicy) -> String {
+        let flags = |flags: Vec<&str>| {
+            let flags = flags.join(" ");
+            format!(
+                "    set(CMAKE_C_FLAGS \"${{CMAKE_C_FLAGS}} {flags}\")\n    \
+                 set(CMAKE_CXX_FLAGS \"${{CMAKE_CXX_FLAGS}} {flags}\")\n",
+                flags = flags
+            )
+        };
+
+        format!(
+            "if(MSVC)\n{}else()\n{}endif()\n",
+            flags(policy.msvc_flags()),
+            flags(policy.gnu_flags())
+        )
+    }
 }
 
+#[allow(non_camel_case_types)]
 pub struct CMake_3_x;
 
 impl Generator for CMake_3_x {
     fn version(&self) -> Version {
         Version::new(3, 1)
     }
+
+    // GNUInstallDirs picks destinations following the platform conventions, e.g. lib64
+    fn install_rules(&self, include_dir: Option<&str>) -> String {
+        let mut rules = String::from("include(GNUInstallDirs)\n");
+        rules += "install(TARGETS ${CBAKE_TARGET} RUNTIME DESTINATION ${CMAKE_INSTALL_BINDIR})\n";
+
+        if let Some(include_dir) = include_dir {
+            rules += &format!(
+                "install(DIRECTORY {} DESTINATION ${{CMAKE_INSTALL_INCLUDEDIR}})\n",
+                path(&format!("{}/", include_dir))
+            );
+        }
+
+        rules
+    }
+
+    fn project_with_version(&self, name: &str, version: &str) -> String {
+        format!("project({} VERSION {})\n\n", name, version)
+    }
+
+    // Consumers get the installed include directory from the INCLUDES DESTINATION of the export
+    fn public_include_dirs(&self, dir_names: &[String]) -> String {
+        let dir_list: Vec<String> = dir_names
+            .iter()
+            .map(|dir| build_interface_path(dir))
+            .collect();
+
+        format!(
+            "target_include_directories(${{CBAKE_TARGET}} PUBLIC\n    {}\n)\n\n",
+            dir_list.join("\n    ")
+        )
+    }
+
+    fn export_package(&self, namespace: &str, include_dirs: &[String]) -> String {
+        let mut export = String::from("include(GNUInstallDirs)\ninclude(CMakePackageConfigHelpers)\n\n");
+
+        export += &format!(
+            "install(TARGETS ${{CBAKE_TARGET}} EXPORT ${{CBAKE_TARGET}}Targets\n    \
+             ARCHIVE DESTINATION ${{CMAKE_INSTALL_LIBDIR}}\n    \
+             LIBRARY DESTINATION ${{CMAKE_INSTALL_LIBDIR}}\n    \
+             RUNTIME DESTINATION ${{CMAKE_INSTALL_BINDIR}}\n    \
+             INCLUDES DESTINATION ${{CMAKE_INSTALL_INCLUDEDIR}}\n)\n\
+             {}\
+             install(EXPORT ${{CBAKE_TARGET}}Targets\n    \
+             NAMESPACE {}::\n    \
+             DESTINATION ${{CMAKE_INSTALL_LIBDIR}}/cmake/${{CBAKE_TARGET}}\n)\n\n",
+            install_include_dirs(include_dirs, "${CMAKE_INSTALL_INCLUDEDIR}"),
+            namespace
+        );
+
+        export += &config_file("");
+        export += &config_version_file("${CMAKE_INSTALL_LIBDIR}/cmake/${CBAKE_TARGET}");
+
+        export
+    }
+
+    // The library directory may be nested like lib/x86_64-linux-gnu, so the way up to the prefix is computed
+    fn pkg_config(&self, package: &PkgConfig) -> String {
+        let mut pkg_config = String::from("include(GNUInstallDirs)\n");
+
+        pkg_config += "file(RELATIVE_PATH PKG_CONFIG_PREFIX\n    \
+                       ${CMAKE_INSTALL_PREFIX}/${CMAKE_INSTALL_LIBDIR}/pkgconfig\n    \
+                       ${CMAKE_INSTALL_PREFIX}\n)\n";
+        pkg_config += &pc_file(
+            package,
+            "${PKG_CONFIG_PREFIX}",
+            "${CMAKE_INSTALL_LIBDIR}",
+            "${CMAKE_INSTALL_INCLUDEDIR}",
+        );
+        pkg_config += "install(FILES ${CMAKE_CURRENT_BINARY_DIR}/${CBAKE_TARGET}.pc \
+                       DESTINATION ${CMAKE_INSTALL_LIBDIR}/pkgconfig)\n";
+
+        pkg_config
+    }
+
+    // target_link_options appeared in cmake 3.13, older versions get the LINK_FLAGS property
+    fn target_settings(&self, settings: &TargetSettings) -> String {
+        let mut commands = String::new();
+
+        if !settings.definitions.is_empty() {
+            commands += &format!(
+                "target_compile_definitions(${{CBAKE_TARGET}} PRIVATE {})\n",
+                arguments(&settings.definitions)
+            );
+        }
+
+        if !settings.compile_options.is_empty() {
+            commands += &format!(
+                "target_compile_options(${{CBAKE_TARGET}} PRIVATE {})\n",
+                arguments(&settings.compile_options)
+            );
+        }
+
+        if !settings.link_options.is_empty() {
+            commands += &format!(
+                "if(COMMAND target_link_options)\n    \
+                 target_link_options(${{CBAKE_TARGET}} PRIVATE {})\n\
+                 else()\n    \
+                 {}\
+                 endif()\n",
+                arguments(&settings.link_options),
+                link_flags_property(&settings.link_options)
+            );
+        }
+
+        if !settings.link_libraries.is_empty() {
+            commands += &format!(
+                "target_link_libraries(${{CBAKE_TARGET}} PRIVATE {})\n",
+                arguments(&settings.link_libraries)
+            );
+        }
+
+        commands
+    }
+
+    // Generator expressions pick the flags when the compiler is known, so the same
+    // CMakeLists.txt works with every compiler
+    fn warnings(&self, policy: &WarningPolicy) -> String {
+        format!(
+            "target_compile_options(${{CBAKE_TARGET}} PRIVATE\n    \
+             \"$<$<CXX_COMPILER_ID:MSVC>:{}>\"\n    \
+             \"$<$<NOT:$<CXX_COMPILER_ID:MSVC>>:{}>\"\n)\n",
+            policy.msvc_flags().join(";"),
+            policy.gnu_flags().join(";")
+        )
+    }
+}
+
+fn link_flags_property(options: &[String]) -> String {
+    format!(
+        "set_property(TARGET ${{CBAKE_TARGET}} APPEND_STRING PROPERTY LINK_FLAGS \" {}\")\n",
+        escape_quoted(&options.join(" "))
+    )
+}
+
+// pkg-config expands ${pcfiledir} to the directory of the .pc file
+fn pc_file(package: &PkgConfig, prefix: &str, lib_dir: &str, include_dir: &str) -> String {
+    let libs: String = package
+        .link_libs
+        .iter()
+        .map(|lib| {
+            if lib.starts_with('-') {
+                format!(" {}", lib)
+            } else {
+                format!(" -l{}", lib)
+            }
+        })
+        .collect();
+
+    format!(
+        r#"file(WRITE ${{CMAKE_CURRENT_BINARY_DIR}}/${{CBAKE_TARGET}}.pc
+"prefix=\${{pcfiledir}}/{prefix}
+libdir=\${{prefix}}/{lib_dir}
+includedir=\${{prefix}}/{include_dir}
+
+Name: {name}
+Description: {description}
+Version: {version}
+Cflags: -I\${{includedir}}
+Libs: -L\${{libdir}} -l${{CBAKE_TARGET}}{libs}
+")
+"#,
+        prefix = prefix,
+        lib_dir = lib_dir,
+        include_dir = include_dir,
+        name = escape_text(package.name),
+        description = escape_text(package.description.unwrap_or(package.name)),
+        version = escape_text(package.version),
+        libs = escape_quoted(&libs)
+    )
+}
+
+// Config.cmake includes the exported targets, <namespace>::<Name> carries everything needed to link
+fn config_file(extra_lines: &str) -> String {
+    format!(
+        r#"file(WRITE ${{CMAKE_CURRENT_BINARY_DIR}}/${{CBAKE_TARGET}}Config.cmake
+    "include(\"\${{CMAKE_CURRENT_LIST_DIR}}/${{CBAKE_TARGET}}Targets.cmake\")\n{}"
+)
+"#,
+        extra_lines
+    )
+}
+
+fn install_include_dirs(dir_names: &[String], destination: &str) -> String {
+    dir_names
+        .iter()
+        .map(|dir| format!("install(DIRECTORY {} DESTINATION {})\n", path(&format!("{}/", dir)), destination))
+        .collect()
+}
+
+fn config_version_file(destination: &str) -> String {
+    format!(
+        "write_basic_package_version_file(${{CMAKE_CURRENT_BINARY_DIR}}/${{CBAKE_TARGET}}ConfigVersion.cmake\n    \
+         VERSION ${{PROJECT_VERSION}}\n    \
+         COMPATIBILITY SameMajorVersion\n)\n\
+         install(FILES\n    \
+         ${{CMAKE_CURRENT_BINARY_DIR}}/${{CBAKE_TARGET}}Config.cmake\n    \
+         ${{CMAKE_CURRENT_BINARY_DIR}}/${{CBAKE_TARGET}}ConfigVersion.cmake\n    \
+         DESTINATION {}\n)\n",
+        destination
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::snapshot;
+    use super::*;
+
+    #[test]
+    fn snapshots() {
+        let dirs = vec![String::from("include")];
+        let link_libs = vec![String::from("pthread")];
+        let package = PkgConfig {
+            name: "demo",
+            version: "1.2.0",
+            description: Some("Demo library"),
+            link_libs: &link_libs,
+        };
+        let settings = TargetSettings {
+            definitions: vec![String::from("USE_FOO")],
+            compile_options: vec![String::from("-fno-exceptions")],
+            link_options: vec![String::from("-Wl,--as-needed")],
+            link_libraries: vec![String::from("pthread")],
+        };
+        let warnings = WarningPolicy {
+            level: crate::warnings::WarningLevel::Pedantic,
+            deny: false,
+        };
+        // Package metadata is written as is, variable references included
+        let escaped_package = PkgConfig {
+            description: Some("Demo ${LIBRARY} \\ \"library\""),
+            ..package
+        };
+
+        let mut snapshots = vec![(
+            String::from("common"),
+            [
+                min_ver(&Version::new(3, 1)),
+                project("demo"),
+                default_build_type("Release"),
+                include_dirs(&dirs),
+                add_executable(&[String::from("main.cpp")]),
+                add_library(&[String::from("demo.cpp")]),
+                enable_testing(),
+                cpack("demo", "1.2.0", "Jane Doe <jane@example.com>", Some("Demo library")),
+            ]
+            .concat(),
+        )];
+        snapshots.push((
+            String::from("escaped"),
+            [
+                cpack("demo", "1.2.0", "${USER} <jane@example.com>", Some("Demo ${LIBRARY}")),
+                CMake_3_x.pkg_config(&escaped_package),
+            ]
+            .concat(),
+        ));
+
+        for (name, generator) in snapshot::generators() {
+            let generated = [
+                generator.min_ver(),
+                generator.project_with_version("demo", "1.2.0"),
+                generator.install_rules(Some("include")),
+                generator.public_include_dirs(&dirs),
+                generator.export_package("demo", &dirs),
+                generator.pkg_config(&package),
+                generator.target_settings(&settings),
+                generator.warnings(&warnings),
+            ];
+
+            snapshots.push((format!("{}_generator", name), generated.join("\n")));
+        }
+
+        snapshot::assert_snapshots(&snapshots);
+    }
+
+    #[test]
+    fn source_paths() {
+        let sources = vec![
+            String::from("main.cpp"),
+            String::from("my file.cpp"),
+            String::from("f(x).cpp"),
+        ];
+        let command = add_executable(&sources);
+
+        assert_eq!(
+            command,
+            "\nadd_executable(${CBAKE_TARGET}\n    main.cpp\n    \"my file.cpp\"\n    \"f(x).cpp\"\n)\n\n"
+        );
+        assert_eq!(command_len(command.trim()), Some(command.trim().len()));
+        assert_eq!(command_len("add_library(a \"b\\\")\" c) tail"), Some(23));
+        assert_eq!(command_len("add_library(a \")\""), None);
+        assert_eq!(
+            include_dirs(&[String::from("include"), String::from("third party")]),
+            "include_directories(\n    include\n    \"third party\"\n)\n\n"
+        );
+    }
+
+    #[test]
+    fn awkward_paths() {
+        let cases = [
+            ("src/main.cpp", "src/main.cpp"),
+            ("a;b.cpp", r#""a\;b.cpp""#),
+            ("${HOME}.cpp", r#""\${HOME}.cpp""#),
+            ("$ENV{PATH}.cpp", r#""\$ENV{PATH}.cpp""#),
+            (r"back\slash.cpp", r#""back\\slash.cpp""#),
+            (r#"say "hi".cpp"#, r#""say \"hi\".cpp""#),
+            ("#hash.cpp", r##""#hash.cpp""##),
+            ("", r#""""#),
+        ];
+
+        for (value, expected) in cases.iter() {
+            assert_eq!(path(value), *expected, "{}", value);
+        }
+
+        // Paths are escaped wherever they are emitted
+        let dirs = vec![String::from("my include"), String::from("a;b>c")];
+        assert_eq!(
+            CMake_3_x.public_include_dirs(&dirs),
+            "target_include_directories(${CBAKE_TARGET} PUBLIC\n    \
+             \"$<BUILD_INTERFACE:${CMAKE_CURRENT_SOURCE_DIR}/my include>\"\n    \
+             \"$<BUILD_INTERFACE:${CMAKE_CURRENT_SOURCE_DIR}/a$<SEMICOLON>b$<ANGLE-R>c>\"\n)\n\n"
+        );
+        assert!(CMake_2_8
+            .install_rules(Some("my include"))
+            .contains("install(DIRECTORY \"my include/\" DESTINATION include)\n"));
+        assert!(CMake_3_x
+            .export_package("demo", &dirs)
+            .contains("install(DIRECTORY \"a\\;b>c/\" DESTINATION ${CMAKE_INSTALL_INCLUDEDIR})\n"));
+        assert_eq!(
+            add_library(&[String::from("${x}.cpp")]),
+            "\nadd_library(${CBAKE_TARGET}\n    \"\\${x}.cpp\"\n)\n\n"
+        );
+
+        // Quoted paths don't end the command early when cbake rewrites it
+        let command = add_executable(&[String::from("f(x;y).cpp"), String::from("\\\")")]);
+        assert_eq!(command_len(command.trim()), Some(command.trim().len()));
+    }
+
+    #[test]
+    fn install_rules() {
+        assert_eq!(
+            CMake_2_8.install_rules(Some("include")),
+            "install(TARGETS ${CBAKE_TARGET} RUNTIME DESTINATION bin)\ninstall(DIRECTORY include/ DESTINATION include)\n"
+        );
+
+        let rules = CMake_3_x.install_rules(None);
+        assert!(rules.starts_with("include(GNUInstallDirs)\n"));
+        assert!(rules.contains("RUNTIME DESTINATION ${CMAKE_INSTALL_BINDIR}"));
+        assert!(!rules.contains("DIRECTORY"));
+    }
+
+    #[test]
+    fn package_config() {
+        let include_dirs = vec![String::from("include")];
+
+        let export = CMake_3_x.export_package("demo", &include_dirs);
+        assert!(export.contains("install(TARGETS ${CBAKE_TARGET} EXPORT ${CBAKE_TARGET}Targets\n"));
+        assert!(export.contains("install(DIRECTORY include/ DESTINATION ${CMAKE_INSTALL_INCLUDEDIR})\n"));
+        assert!(export.contains("    NAMESPACE demo::\n"));
+        assert!(export.contains("\"include(\\\"\\${CMAKE_CURRENT_LIST_DIR}/${CBAKE_TARGET}Targets.cmake\\\")\\n\"\n"));
+        assert!(export.contains("write_basic_package_version_file(${CMAKE_CURRENT_BINARY_DIR}/${CBAKE_TARGET}ConfigVersion.cmake\n"));
+
+        let export = CMake_2_8.export_package("demo", &include_dirs);
+        assert!(!export.contains("INCLUDES DESTINATION"));
+        assert!(export.contains("set(${CBAKE_TARGET}_INCLUDE_DIRS"));
+        assert!(export.contains("DESTINATION lib/cmake/${CBAKE_TARGET}\n"));
+
+        assert_eq!(CMake_3_x.project_with_version("demo", "1.2.0"), "project(demo VERSION 1.2.0)\n\n");
+        assert_eq!(
+            CMake_2_8.project_with_version("demo", "1.2.0"),
+            "project(demo)\nset(PROJECT_VERSION 1.2.0)\n\n"
+        );
+    }
+
+    #[test]
+    fn pkg_config_file() {
+        let link_libs = vec![String::from("pthread"), String::from("-lm")];
+        let package = PkgConfig {
+            name: "demo",
+            version: "1.2.0",
+            description: Some("Demo \"library\""),
+            link_libs: &link_libs,
+        };
+
+        let pkg_config = CMake_2_8.pkg_config(&package);
+        assert!(pkg_config.starts_with("file(WRITE ${CMAKE_CURRENT_BINARY_DIR}/${CBAKE_TARGET}.pc\n\"prefix=\\${pcfiledir}/../..\n"));
+        assert!(pkg_config.contains("libdir=\\${prefix}/lib\nincludedir=\\${prefix}/include\n"));
+        assert!(pkg_config.contains("Description: Demo \\\"library\\\"\nVersion: 1.2.0\n"));
+        assert!(pkg_config.contains("Libs: -L\\${libdir} -l${CBAKE_TARGET} -lpthread -lm\n\")\n"));
+        assert!(pkg_config.ends_with("DESTINATION lib/pkgconfig)\n"));
+
+        let package = PkgConfig {
+            description: None,
+            link_libs: &[],
+            ..package
+        };
+
+        let pkg_config = CMake_3_x.pkg_config(&package);
+        assert!(pkg_config.contains("prefix=\\${pcfiledir}/${PKG_CONFIG_PREFIX}\nlibdir=\\${prefix}/${CMAKE_INSTALL_LIBDIR}\n"));
+        assert!(pkg_config.contains("Description: demo\n"));
+
+        let package = PkgConfig {
+            description: Some("${DESCRIPTION}"),
+            ..package
+        };
+        assert!(CMake_3_x.pkg_config(&package).contains("Description: \\${DESCRIPTION}\n"));
+        assert!(pkg_config.contains("Libs: -L\\${libdir} -l${CBAKE_TARGET}\n"));
+        assert!(pkg_config.ends_with("DESTINATION ${CMAKE_INSTALL_LIBDIR}/pkgconfig)\n"));
+    }
+
+    #[test]
+    fn target_settings() {
+        let settings = TargetSettings {
+            definitions: vec![String::from("USE_FOO"), String::from("NAME=\"demo app\"")],
+            compile_options: vec![String::from("-Wall")],
+            link_options: vec![String::from("-Wl,--as-needed")],
+            link_libraries: vec![String::from("pthread")],
+        };
+
+        assert_eq!(
+            CMake_3_x.target_settings(&settings),
+            "target_compile_definitions(${CBAKE_TARGET} PRIVATE USE_FOO \"NAME=\\\"demo app\\\"\")\n\
+             target_compile_options(${CBAKE_TARGET} PRIVATE -Wall)\n\
+             if(COMMAND target_link_options)\n    \
+             target_link_options(${CBAKE_TARGET} PRIVATE -Wl,--as-needed)\n\
+             else()\n    \
+             set_property(TARGET ${CBAKE_TARGET} APPEND_STRING PROPERTY LINK_FLAGS \" -Wl,--as-needed\")\n\
+             endif()\n\
+             target_link_libraries(${CBAKE_TARGET} PRIVATE pthread)\n"
+        );
+
+        assert_eq!(
+            CMake_2_8.target_settings(&settings),
+            "add_definitions(-DUSE_FOO \"-DNAME=\\\"demo app\\\"\")\n\
+             set(CMAKE_C_FLAGS \"${CMAKE_C_FLAGS} -Wall\")\n\
+             set(CMAKE_CXX_FLAGS \"${CMAKE_CXX_FLAGS} -Wall\")\n\
+             set_property(TARGET ${CBAKE_TARGET} APPEND_STRING PROPERTY LINK_FLAGS \" -Wl,--as-needed\")\n\
+             target_link_libraries(${CBAKE_TARGET} pthread)\n"
+        );
+
+        assert!(TargetSettings::default().is_empty());
+        assert_eq!(CMake_3_x.target_settings(&TargetSettings::default()), "");
+    }
+
+    #[test]
+    fn warnings() {
+        let policy = WarningPolicy {
+            level: crate::warnings::WarningLevel::Strict,
+            deny: true,
+        };
+
+        assert_eq!(
+            CMake_3_x.warnings(&policy),
+            "target_compile_options(${CBAKE_TARGET} PRIVATE\n    \
+             \"$<$<CXX_COMPILER_ID:MSVC>:/W4;/WX>\"\n    \
+             \"$<$<NOT:$<CXX_COMPILER_ID:MSVC>>:-Wall;-Wextra;-Werror>\"\n)\n"
+        );
+        assert_eq!(
+            CMake_2_8.warnings(&policy),
+            "if(MSVC)\n    \
+             set(CMAKE_C_FLAGS \"${CMAKE_C_FLAGS} /W4 /WX\")\n    \
+             set(CMAKE_CXX_FLAGS \"${CMAKE_CXX_FLAGS} /W4 /WX\")\n\
+             else()\n    \
+             set(CMAKE_C_FLAGS \"${CMAKE_C_FLAGS} -Wall -Wextra -Werror\")\n    \
+             set(CMAKE_CXX_FLAGS \"${CMAKE_CXX_FLAGS} -Wall -Wextra -Werror\")\n\
+             endif()\n"
+        );
+    }
+
+    #[test]
+    fn default_build_type_snippet() {
+        assert_eq!(
+            default_build_type("Debug"),
+            "if(NOT CMAKE_BUILD_TYPE AND NOT CMAKE_CONFIGURATION_TYPES)\n    \
+             set(CMAKE_BUILD_TYPE \"Debug\" CACHE STRING \"Choose the type of build\" FORCE)\n    \
+             set_property(CACHE CMAKE_BUILD_TYPE PROPERTY STRINGS Debug Release RelWithDebInfo MinSizeRel)\n\
+             endif()\n\n"
+        );
+    }
+
+    // Configures a project with the snippet using the real cmake
+    #[test]
+    #[ignore = "needs cmake, run with `cargo test -- --ignored`"]
+    fn default_build_type_cmake() {
+        let scratch = crate::scratch::ScratchDir::new("default-build-type");
+        let project_dir = scratch.path();
+        std::fs::write(
+            project_dir.join("CMakeLists.txt"),
+            format!(
+                "cmake_minimum_required(VERSION 3.1)\nproject(check NONE)\n{}message(STATUS \"build-type=[${{CMAKE_BUILD_TYPE}}]\")\n",
+                default_build_type("Debug")
+            ),
+        )
+        .unwrap();
+
+        let configure = |build_dir: &str, args: &[&str]| {
+            let output = std::process::Command::new(super::super::exec::cmake_program())
+                .args(args)
+                .arg(format!("-B{}", project_dir.join(build_dir).display()))
+                .arg(format!("-H{}", project_dir.display()))
+                .output()
+                .unwrap();
+            let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+
+            assert!(output.status.success(), "{}{}", stdout, String::from_utf8_lossy(&output.stderr));
+            stdout
+        };
+
+        assert!(configure("default", &[]).contains("build-type=[Debug]"));
+        assert!(configure("release", &["-DCMAKE_BUILD_TYPE=Release"]).contains("build-type=[Release]"));
+        assert!(configure("multi", &["-DCMAKE_CONFIGURATION_TYPES=Debug;Release"]).contains("build-type=[]"));
+    }
+
+    #[test]
+    fn cpack_settings() {
+        let escaped = cpack("demo", "1.0.0", "${USER}", Some("costs $5"));
+        let cpack = cpack("demo", "1.0.0", "Jane \"JD\" Doe <jane@example.com>", None);
+
+        assert!(cpack.contains("set(CPACK_PACKAGE_VERSION \"1.0.0\")\n"));
+        assert!(cpack.contains("set(CPACK_DEBIAN_PACKAGE_MAINTAINER \"Jane \\\"JD\\\" Doe <jane@example.com>\")\n"));
+        assert!(!cpack.contains("CPACK_PACKAGE_DESCRIPTION_SUMMARY"));
+        assert!(cpack.ends_with("set(CPACK_GENERATOR \"TGZ;DEB\")\ninclude(CPack)\n"));
+
+        assert!(escaped.contains("set(CPACK_PACKAGE_CONTACT \"\\${USER}\")\n"));
+        assert!(escaped.contains("set(CPACK_PACKAGE_DESCRIPTION_SUMMARY \"costs \\$5\")\n"));
+    }
 }
@@ -1,7 +1,11 @@
 pub mod builder;
 pub mod exec;
 pub mod generator;
+pub mod toolchain;
 pub mod version;
 
+#[cfg(test)]
+mod snapshot;
+
 pub use builder::Builder;
 pub use version::Version;
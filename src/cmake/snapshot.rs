@@ -0,0 +1,70 @@
+/// This module compares generated CMake code with the golden files in src/cmake/snapshots.
+/// Run the tests with CBAKE_UPDATE_SNAPSHOTS=1 to rewrite the files after an intended change,
+/// then review them with git diff
+use super::generator::{CMake_2_8, CMake_3_x, Generator};
+
+use std::{env, fs, path::PathBuf};
+
+const UPDATE_VAR: &str = "CBAKE_UPDATE_SNAPSHOTS";
+
+// Every generator gets its own snapshots, a new generator has to be added here
+pub fn generators() -> Vec<(&'static str, Box<dyn Generator>)> {
+    vec![("cmake_2_8", Box::new(CMake_2_8)), ("cmake_3_x", Box::new(CMake_3_x))]
+}
+
+fn snapshot_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("src/cmake/snapshots")
+        .join(format!("{}.cmake", name))
+}
+
+// Checks all snapshots first, so a single run lists every mismatch
+pub fn assert_snapshots(snapshots: &[(String, String)]) {
+    let update = env::var_os(UPDATE_VAR).is_some_and(|value| value != "0");
+    let mut failures = Vec::new();
+
+    for (name, actual) in snapshots {
+        let path = snapshot_path(name);
+
+        if update {
+            fs::write(&path, actual).unwrap_or_else(|e| panic!("cannot write {}: {}", path.display(), e));
+            continue;
+        }
+
+        match fs::read_to_string(&path) {
+            Ok(expected) if expected == *actual => {}
+            Ok(expected) => failures.push(format!("{} differs:\n{}", path.display(), diff(&expected, actual))),
+            Err(e) => failures.push(format!("cannot read {}: {}", path.display(), e)),
+        }
+    }
+
+    if !failures.is_empty() {
+        panic!(
+            "{}\nRun the tests with {}=1 if the changes are intended",
+            failures.join("\n"),
+            UPDATE_VAR
+        );
+    }
+}
+
+// Lines of the expected and the actual text which don't match, prefixed with - and +
+fn diff(expected: &str, actual: &str) -> String {
+    let expected: Vec<&str> = expected.lines().collect();
+    let actual: Vec<&str> = actual.lines().collect();
+    let mut diff = String::new();
+
+    for i in 0..expected.len().max(actual.len()) {
+        let (old, new) = (expected.get(i), actual.get(i));
+
+        if old != new {
+            if let Some(old) = old {
+                diff += &format!("{:4} - {}\n", i + 1, old);
+            }
+            if let Some(new) = new {
+                diff += &format!("{:4} + {}\n", i + 1, new);
+            }
+        }
+    }
+
+    diff
+}
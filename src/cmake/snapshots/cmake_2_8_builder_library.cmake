@@ -0,0 +1,44 @@
+cmake_minimum_required(VERSION 2.8)
+
+project(demo)
+set(PROJECT_VERSION 1.2.0)
+
+#-------- Warning: This section will be overwritten by cbake utility. Don't change it manually if you will use it --------
+set(CBAKE_TARGET demo)
+
+add_library(${CBAKE_TARGET}
+    demo.cpp
+)
+
+#--------------------------------------------------------------------------------------------------------------------------
+
+include_directories(
+    include
+)
+
+#-------- Package config: lets other projects find_package(demo) the installed library --------
+include(CMakePackageConfigHelpers)
+
+install(TARGETS ${CBAKE_TARGET} EXPORT ${CBAKE_TARGET}Targets
+    ARCHIVE DESTINATION lib
+    LIBRARY DESTINATION lib
+    RUNTIME DESTINATION bin
+)
+install(DIRECTORY include/ DESTINATION include)
+install(EXPORT ${CBAKE_TARGET}Targets
+    NAMESPACE demo::
+    DESTINATION lib/cmake/${CBAKE_TARGET}
+)
+
+file(WRITE ${CMAKE_CURRENT_BINARY_DIR}/${CBAKE_TARGET}Config.cmake
+    "include(\"\${CMAKE_CURRENT_LIST_DIR}/${CBAKE_TARGET}Targets.cmake\")\nset(${CBAKE_TARGET}_INCLUDE_DIRS \"\${CMAKE_CURRENT_LIST_DIR}/../../../include\")\n"
+)
+write_basic_package_version_file(${CMAKE_CURRENT_BINARY_DIR}/${CBAKE_TARGET}ConfigVersion.cmake
+    VERSION ${PROJECT_VERSION}
+    COMPATIBILITY SameMajorVersion
+)
+install(FILES
+    ${CMAKE_CURRENT_BINARY_DIR}/${CBAKE_TARGET}Config.cmake
+    ${CMAKE_CURRENT_BINARY_DIR}/${CBAKE_TARGET}ConfigVersion.cmake
+    DESTINATION lib/cmake/${CBAKE_TARGET}
+)
@@ -0,0 +1,13 @@
+cmake_minimum_required(VERSION 2.8)
+
+project(demo)
+
+#-------- Warning: This section will be overwritten by cbake utility. Don't change it manually if you will use it --------
+set(CBAKE_TARGET demo)
+
+add_executable(${CBAKE_TARGET}
+    src/main.cpp
+    src/util.cpp
+)
+
+#--------------------------------------------------------------------------------------------------------------------------
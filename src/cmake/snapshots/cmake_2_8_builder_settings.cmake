@@ -0,0 +1,39 @@
+cmake_minimum_required(VERSION 2.8)
+
+project(demo)
+
+#-------- Warning: This section will be overwritten by cbake utility. Don't change it manually if you will use it --------
+set(CBAKE_TARGET demo)
+
+add_executable(${CBAKE_TARGET}
+    src/main.cpp
+    src/util.cpp
+)
+
+#--------------------------------------------------------------------------------------------------------------------------
+
+#-------- Warning: This section will be overwritten by cbake build. Don't change it manually --------
+add_definitions(-DUSE_FOO -DLEVEL=2)
+set(CMAKE_C_FLAGS "${CMAKE_C_FLAGS} -fno-exceptions")
+set(CMAKE_CXX_FLAGS "${CMAKE_CXX_FLAGS} -fno-exceptions")
+set_property(TARGET ${CBAKE_TARGET} APPEND_STRING PROPERTY LINK_FLAGS " -Wl,--as-needed")
+target_link_libraries(${CBAKE_TARGET} pthread)
+if(MSVC)
+    set(CMAKE_C_FLAGS "${CMAKE_C_FLAGS} /W4 /WX")
+    set(CMAKE_CXX_FLAGS "${CMAKE_CXX_FLAGS} /W4 /WX")
+else()
+    set(CMAKE_C_FLAGS "${CMAKE_C_FLAGS} -Wall -Wextra -Werror")
+    set(CMAKE_CXX_FLAGS "${CMAKE_CXX_FLAGS} -Wall -Wextra -Werror")
+endif()
+if(CBAKE_PROFILE STREQUAL "release")
+    set(CMAKE_C_FLAGS "${CMAKE_C_FLAGS} -march=native")
+    set(CMAKE_CXX_FLAGS "${CMAKE_CXX_FLAGS} -march=native")
+    set_property(TARGET ${CBAKE_TARGET} APPEND_STRING PROPERTY LINK_FLAGS " -s")
+endif()
+if(CBAKE_COMPILE_FLAGS)
+    set_property(TARGET ${CBAKE_TARGET} APPEND_STRING PROPERTY COMPILE_FLAGS " ${CBAKE_COMPILE_FLAGS}")
+endif()
+if(CBAKE_LINK_FLAGS)
+    set_property(TARGET ${CBAKE_TARGET} APPEND_STRING PROPERTY LINK_FLAGS " ${CBAKE_LINK_FLAGS}")
+endif()
+#-------- End of the build settings section --------
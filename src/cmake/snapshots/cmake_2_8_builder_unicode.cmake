@@ -0,0 +1,14 @@
+cmake_minimum_required(VERSION 2.8)
+
+project(café)
+set(PROJECT_VERSION 0.1.0)
+
+#-------- Warning: This section will be overwritten by cbake utility. Don't change it manually if you will use it --------
+set(CBAKE_TARGET caf_)
+
+add_executable(${CBAKE_TARGET}
+    src/main.cpp
+    src/util.cpp
+)
+
+#--------------------------------------------------------------------------------------------------------------------------
@@ -0,0 +1,67 @@
+cmake_minimum_required(VERSION 2.8)
+
+
+project(demo)
+set(PROJECT_VERSION 1.2.0)
+
+
+install(TARGETS ${CBAKE_TARGET} RUNTIME DESTINATION bin)
+install(DIRECTORY include/ DESTINATION include)
+
+include_directories(
+    include
+)
+
+
+include(CMakePackageConfigHelpers)
+
+install(TARGETS ${CBAKE_TARGET} EXPORT ${CBAKE_TARGET}Targets
+    ARCHIVE DESTINATION lib
+    LIBRARY DESTINATION lib
+    RUNTIME DESTINATION bin
+)
+install(DIRECTORY include/ DESTINATION include)
+install(EXPORT ${CBAKE_TARGET}Targets
+    NAMESPACE demo::
+    DESTINATION lib/cmake/${CBAKE_TARGET}
+)
+
+file(WRITE ${CMAKE_CURRENT_BINARY_DIR}/${CBAKE_TARGET}Config.cmake
+    "include(\"\${CMAKE_CURRENT_LIST_DIR}/${CBAKE_TARGET}Targets.cmake\")\nset(${CBAKE_TARGET}_INCLUDE_DIRS \"\${CMAKE_CURRENT_LIST_DIR}/../../../include\")\n"
+)
+write_basic_package_version_file(${CMAKE_CURRENT_BINARY_DIR}/${CBAKE_TARGET}ConfigVersion.cmake
+    VERSION ${PROJECT_VERSION}
+    COMPATIBILITY SameMajorVersion
+)
+install(FILES
+    ${CMAKE_CURRENT_BINARY_DIR}/${CBAKE_TARGET}Config.cmake
+    ${CMAKE_CURRENT_BINARY_DIR}/${CBAKE_TARGET}ConfigVersion.cmake
+    DESTINATION lib/cmake/${CBAKE_TARGET}
+)
+
+file(WRITE ${CMAKE_CURRENT_BINARY_DIR}/${CBAKE_TARGET}.pc
+"prefix=\${pcfiledir}/../..
+libdir=\${prefix}/lib
+includedir=\${prefix}/include
+
+Name: demo
+Description: Demo library
+Version: 1.2.0
+Cflags: -I\${includedir}
+Libs: -L\${libdir} -l${CBAKE_TARGET} -lpthread
+")
+install(FILES ${CMAKE_CURRENT_BINARY_DIR}/${CBAKE_TARGET}.pc DESTINATION lib/pkgconfig)
+
+add_definitions(-DUSE_FOO)
+set(CMAKE_C_FLAGS "${CMAKE_C_FLAGS} -fno-exceptions")
+set(CMAKE_CXX_FLAGS "${CMAKE_CXX_FLAGS} -fno-exceptions")
+set_property(TARGET ${CBAKE_TARGET} APPEND_STRING PROPERTY LINK_FLAGS " -Wl,--as-needed")
+target_link_libraries(${CBAKE_TARGET} pthread)
+
+if(MSVC)
+    set(CMAKE_C_FLAGS "${CMAKE_C_FLAGS} /W4 /permissive-")
+    set(CMAKE_CXX_FLAGS "${CMAKE_CXX_FLAGS} /W4 /permissive-")
+else()
+    set(CMAKE_C_FLAGS "${CMAKE_C_FLAGS} -Wall -Wextra -Wpedantic")
+    set(CMAKE_CXX_FLAGS "${CMAKE_CXX_FLAGS} -Wall -Wextra -Wpedantic")
+endif()
@@ -0,0 +1,26 @@
+cmake_minimum_required(VERSION 3.1)
+
+project(demo VERSION 0.1.0)
+
+if(NOT CMAKE_BUILD_TYPE AND NOT CMAKE_CONFIGURATION_TYPES)
+    set(CMAKE_BUILD_TYPE "Debug" CACHE STRING "Choose the type of build" FORCE)
+    set_property(CACHE CMAKE_BUILD_TYPE PROPERTY STRINGS Debug Release RelWithDebInfo MinSizeRel)
+endif()
+
+set(CMAKE_CXX_STANDARD 11)
+
+enable_testing()
+
+include_directories(
+    include
+)
+
+#-------- Warning: This section will be overwritten by cbake utility. Don't change it manually if you will use it --------
+set(CBAKE_TARGET demo)
+
+add_executable(${CBAKE_TARGET}
+    src/main.cpp
+    src/util.cpp
+)
+
+#--------------------------------------------------------------------------------------------------------------------------
@@ -0,0 +1,49 @@
+cmake_minimum_required(VERSION 3.1)
+
+project(demo)
+
+#-------- Warning: This section will be overwritten by cbake utility. Don't change it manually if you will use it --------
+set(CBAKE_TARGET demo)
+
+add_library(${CBAKE_TARGET}
+    "my file.cpp"
+    "a\;b.cpp"
+    "\${x}.cpp"
+)
+
+#--------------------------------------------------------------------------------------------------------------------------
+
+target_include_directories(${CBAKE_TARGET} PUBLIC
+    $<BUILD_INTERFACE:${CMAKE_CURRENT_SOURCE_DIR}/include>
+    "$<BUILD_INTERFACE:${CMAKE_CURRENT_SOURCE_DIR}/third party/include>"
+)
+
+#-------- Package config: lets other projects find_package(demo) the installed library --------
+include(GNUInstallDirs)
+include(CMakePackageConfigHelpers)
+
+install(TARGETS ${CBAKE_TARGET} EXPORT ${CBAKE_TARGET}Targets
+    ARCHIVE DESTINATION ${CMAKE_INSTALL_LIBDIR}
+    LIBRARY DESTINATION ${CMAKE_INSTALL_LIBDIR}
+    RUNTIME DESTINATION ${CMAKE_INSTALL_BINDIR}
+    INCLUDES DESTINATION ${CMAKE_INSTALL_INCLUDEDIR}
+)
+install(DIRECTORY include/ DESTINATION ${CMAKE_INSTALL_INCLUDEDIR})
+install(DIRECTORY "third party/include/" DESTINATION ${CMAKE_INSTALL_INCLUDEDIR})
+install(EXPORT ${CBAKE_TARGET}Targets
+    NAMESPACE demo::
+    DESTINATION ${CMAKE_INSTALL_LIBDIR}/cmake/${CBAKE_TARGET}
+)
+
+file(WRITE ${CMAKE_CURRENT_BINARY_DIR}/${CBAKE_TARGET}Config.cmake
+    "include(\"\${CMAKE_CURRENT_LIST_DIR}/${CBAKE_TARGET}Targets.cmake\")\n"
+)
+write_basic_package_version_file(${CMAKE_CURRENT_BINARY_DIR}/${CBAKE_TARGET}ConfigVersion.cmake
+    VERSION ${PROJECT_VERSION}
+    COMPATIBILITY SameMajorVersion
+)
+install(FILES
+    ${CMAKE_CURRENT_BINARY_DIR}/${CBAKE_TARGET}Config.cmake
+    ${CMAKE_CURRENT_BINARY_DIR}/${CBAKE_TARGET}ConfigVersion.cmake
+    DESTINATION ${CMAKE_INSTALL_LIBDIR}/cmake/${CBAKE_TARGET}
+)
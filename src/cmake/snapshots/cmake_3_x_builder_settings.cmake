@@ -0,0 +1,42 @@
+cmake_minimum_required(VERSION 3.1)
+
+project(demo)
+
+#-------- Warning: This section will be overwritten by cbake utility. Don't change it manually if you will use it --------
+set(CBAKE_TARGET demo)
+
+add_executable(${CBAKE_TARGET}
+    src/main.cpp
+    src/util.cpp
+)
+
+#--------------------------------------------------------------------------------------------------------------------------
+
+#-------- Warning: This section will be overwritten by cbake build. Don't change it manually --------
+target_compile_definitions(${CBAKE_TARGET} PRIVATE USE_FOO LEVEL=2)
+target_compile_options(${CBAKE_TARGET} PRIVATE -fno-exceptions)
+if(COMMAND target_link_options)
+    target_link_options(${CBAKE_TARGET} PRIVATE -Wl,--as-needed)
+else()
+    set_property(TARGET ${CBAKE_TARGET} APPEND_STRING PROPERTY LINK_FLAGS " -Wl,--as-needed")
+endif()
+target_link_libraries(${CBAKE_TARGET} PRIVATE pthread)
+target_compile_options(${CBAKE_TARGET} PRIVATE
+    "$<$<CXX_COMPILER_ID:MSVC>:/W4;/WX>"
+    "$<$<NOT:$<CXX_COMPILER_ID:MSVC>>:-Wall;-Wextra;-Werror>"
+)
+if(CBAKE_PROFILE STREQUAL "release")
+    target_compile_options(${CBAKE_TARGET} PRIVATE -march=native)
+    if(COMMAND target_link_options)
+        target_link_options(${CBAKE_TARGET} PRIVATE -s)
+    else()
+        set_property(TARGET ${CBAKE_TARGET} APPEND_STRING PROPERTY LINK_FLAGS " -s")
+    endif()
+endif()
+if(CBAKE_COMPILE_FLAGS)
+    set_property(TARGET ${CBAKE_TARGET} APPEND_STRING PROPERTY COMPILE_FLAGS " ${CBAKE_COMPILE_FLAGS}")
+endif()
+if(CBAKE_LINK_FLAGS)
+    set_property(TARGET ${CBAKE_TARGET} APPEND_STRING PROPERTY LINK_FLAGS " ${CBAKE_LINK_FLAGS}")
+endif()
+#-------- End of the build settings section --------
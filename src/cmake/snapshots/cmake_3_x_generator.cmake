@@ -0,0 +1,74 @@
+cmake_minimum_required(VERSION 3.1)
+
+
+project(demo VERSION 1.2.0)
+
+
+include(GNUInstallDirs)
+install(TARGETS ${CBAKE_TARGET} RUNTIME DESTINATION ${CMAKE_INSTALL_BINDIR})
+install(DIRECTORY include/ DESTINATION ${CMAKE_INSTALL_INCLUDEDIR})
+
+target_include_directories(${CBAKE_TARGET} PUBLIC
+    $<BUILD_INTERFACE:${CMAKE_CURRENT_SOURCE_DIR}/include>
+)
+
+
+include(GNUInstallDirs)
+include(CMakePackageConfigHelpers)
+
+install(TARGETS ${CBAKE_TARGET} EXPORT ${CBAKE_TARGET}Targets
+    ARCHIVE DESTINATION ${CMAKE_INSTALL_LIBDIR}
+    LIBRARY DESTINATION ${CMAKE_INSTALL_LIBDIR}
+    RUNTIME DESTINATION ${CMAKE_INSTALL_BINDIR}
+    INCLUDES DESTINATION ${CMAKE_INSTALL_INCLUDEDIR}
+)
+install(DIRECTORY include/ DESTINATION ${CMAKE_INSTALL_INCLUDEDIR})
+install(EXPORT ${CBAKE_TARGET}Targets
+    NAMESPACE demo::
+    DESTINATION ${CMAKE_INSTALL_LIBDIR}/cmake/${CBAKE_TARGET}
+)
+
+file(WRITE ${CMAKE_CURRENT_BINARY_DIR}/${CBAKE_TARGET}Config.cmake
+    "include(\"\${CMAKE_CURRENT_LIST_DIR}/${CBAKE_TARGET}Targets.cmake\")\n"
+)
+write_basic_package_version_file(${CMAKE_CURRENT_BINARY_DIR}/${CBAKE_TARGET}ConfigVersion.cmake
+    VERSION ${PROJECT_VERSION}
+    COMPATIBILITY SameMajorVersion
+)
+install(FILES
+    ${CMAKE_CURRENT_BINARY_DIR}/${CBAKE_TARGET}Config.cmake
+    ${CMAKE_CURRENT_BINARY_DIR}/${CBAKE_TARGET}ConfigVersion.cmake
+    DESTINATION ${CMAKE_INSTALL_LIBDIR}/cmake/${CBAKE_TARGET}
+)
+
+include(GNUInstallDirs)
+file(RELATIVE_PATH PKG_CONFIG_PREFIX
+    ${CMAKE_INSTALL_PREFIX}/${CMAKE_INSTALL_LIBDIR}/pkgconfig
+    ${CMAKE_INSTALL_PREFIX}
+)
+file(WRITE ${CMAKE_CURRENT_BINARY_DIR}/${CBAKE_TARGET}.pc
+"prefix=\${pcfiledir}/${PKG_CONFIG_PREFIX}
+libdir=\${prefix}/${CMAKE_INSTALL_LIBDIR}
+includedir=\${prefix}/${CMAKE_INSTALL_INCLUDEDIR}
+
+Name: demo
+Description: Demo library
+Version: 1.2.0
+Cflags: -I\${includedir}
+Libs: -L\${libdir} -l${CBAKE_TARGET} -lpthread
+")
+install(FILES ${CMAKE_CURRENT_BINARY_DIR}/${CBAKE_TARGET}.pc DESTINATION ${CMAKE_INSTALL_LIBDIR}/pkgconfig)
+
+target_compile_definitions(${CBAKE_TARGET} PRIVATE USE_FOO)
+target_compile_options(${CBAKE_TARGET} PRIVATE -fno-exceptions)
+if(COMMAND target_link_options)
+    target_link_options(${CBAKE_TARGET} PRIVATE -Wl,--as-needed)
+else()
+    set_property(TARGET ${CBAKE_TARGET} APPEND_STRING PROPERTY LINK_FLAGS " -Wl,--as-needed")
+endif()
+target_link_libraries(${CBAKE_TARGET} PRIVATE pthread)
+
+target_compile_options(${CBAKE_TARGET} PRIVATE
+    "$<$<CXX_COMPILER_ID:MSVC>:/W4;/permissive->"
+    "$<$<NOT:$<CXX_COMPILER_ID:MSVC>>:-Wall;-Wextra;-Wpedantic>"
+)
@@ -0,0 +1,33 @@
+cmake_minimum_required(VERSION 3.1)
+
+project(demo)
+
+if(NOT CMAKE_BUILD_TYPE AND NOT CMAKE_CONFIGURATION_TYPES)
+    set(CMAKE_BUILD_TYPE "Release" CACHE STRING "Choose the type of build" FORCE)
+    set_property(CACHE CMAKE_BUILD_TYPE PROPERTY STRINGS Debug Release RelWithDebInfo MinSizeRel)
+endif()
+
+include_directories(
+    include
+)
+
+
+add_executable(${CBAKE_TARGET}
+    main.cpp
+)
+
+
+add_library(${CBAKE_TARGET}
+    demo.cpp
+)
+
+enable_testing()
+
+set(CPACK_PACKAGE_NAME "demo")
+set(CPACK_PACKAGE_VERSION "1.2.0")
+set(CPACK_PACKAGE_CONTACT "Jane Doe <jane@example.com>")
+set(CPACK_DEBIAN_PACKAGE_MAINTAINER "Jane Doe <jane@example.com>")
+set(CPACK_PACKAGE_DESCRIPTION_SUMMARY "Demo library")
+set(CPACK_PACKAGE_FILE_NAME "${CPACK_PACKAGE_NAME}-${CPACK_PACKAGE_VERSION}-${CMAKE_SYSTEM_NAME}-${CMAKE_SYSTEM_PROCESSOR}")
+set(CPACK_GENERATOR "TGZ;DEB")
+include(CPack)
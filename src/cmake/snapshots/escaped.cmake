@@ -0,0 +1,25 @@
+set(CPACK_PACKAGE_NAME "demo")
+set(CPACK_PACKAGE_VERSION "1.2.0")
+set(CPACK_PACKAGE_CONTACT "\${USER} <jane@example.com>")
+set(CPACK_DEBIAN_PACKAGE_MAINTAINER "\${USER} <jane@example.com>")
+set(CPACK_PACKAGE_DESCRIPTION_SUMMARY "Demo \${LIBRARY}")
+set(CPACK_PACKAGE_FILE_NAME "${CPACK_PACKAGE_NAME}-${CPACK_PACKAGE_VERSION}-${CMAKE_SYSTEM_NAME}-${CMAKE_SYSTEM_PROCESSOR}")
+set(CPACK_GENERATOR "TGZ;DEB")
+include(CPack)
+include(GNUInstallDirs)
+file(RELATIVE_PATH PKG_CONFIG_PREFIX
+    ${CMAKE_INSTALL_PREFIX}/${CMAKE_INSTALL_LIBDIR}/pkgconfig
+    ${CMAKE_INSTALL_PREFIX}
+)
+file(WRITE ${CMAKE_CURRENT_BINARY_DIR}/${CBAKE_TARGET}.pc
+"prefix=\${pcfiledir}/${PKG_CONFIG_PREFIX}
+libdir=\${prefix}/${CMAKE_INSTALL_LIBDIR}
+includedir=\${prefix}/${CMAKE_INSTALL_INCLUDEDIR}
+
+Name: demo
+Description: Demo \${LIBRARY} \\ \"library\"
+Version: 1.2.0
+Cflags: -I\${includedir}
+Libs: -L\${libdir} -l${CBAKE_TARGET} -lpthread
+")
+install(FILES ${CMAKE_CURRENT_BINARY_DIR}/${CBAKE_TARGET}.pc DESTINATION ${CMAKE_INSTALL_LIBDIR}/pkgconfig)
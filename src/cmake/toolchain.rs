@@ -0,0 +1,288 @@
+/// This module generates cmake toolchain files for cross-compilation
+use super::generator;
+use crate::manifest::TargetConfig;
+
+use std::{
+    env,
+    error::Error,
+    fmt,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+// Represents a cross-compilation target given by a triple like aarch64-linux-gnu
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Target {
+    triple: String,
+    arch: String,
+    system: System,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum System {
+    Linux,
+    Android,
+    Windows,
+    Darwin,
+    FreeBSD,
+    Generic, // Bare metal targets like arm-none-eabi
+}
+
+impl Target {
+    pub fn triple(&self) -> &str {
+        &self.triple
+    }
+
+    pub fn arch(&self) -> &str {
+        &self.arch
+    }
+
+    pub fn system(&self) -> System {
+        self.system
+    }
+
+    // Returns true if executables built for this target can be run on the current machine
+    pub fn is_host(&self) -> bool {
+        let host_system = match env::consts::OS {
+            "linux" => System::Linux,
+            "android" => System::Android,
+            "windows" => System::Windows,
+            "macos" => System::Darwin,
+            "freebsd" => System::FreeBSD,
+            _ => return false,
+        };
+
+        normalize_arch(&self.arch) == env::consts::ARCH && self.system == host_system
+    }
+
+    // Name of an environment variable that holds a runner command for this target, e.g.
+    // CBAKE_TARGET_AARCH64_LINUX_GNU_RUNNER="qemu-aarch64 -L /usr/aarch64-linux-gnu"
+    pub fn runner_var(&self) -> String {
+        let triple: String = self
+            .triple
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+            .collect();
+
+        format!("CBAKE_TARGET_{}_RUNNER", triple)
+    }
+
+    // Sysroot given by the user or the directory where distribution cross toolchains install
+    // target libraries (e.g. /usr/aarch64-linux-gnu on Debian)
+    fn find_root_path(&self, sysroot: Option<&Path>) -> Option<PathBuf> {
+        if let Some(sysroot) = sysroot {
+            return Some(sysroot.to_path_buf());
+        }
+
+        let distro_root = Path::new("/usr").join(&self.triple);
+
+        if distro_root.is_dir() {
+            Some(distro_root)
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.triple)
+    }
+}
+
+impl FromStr for Target {
+    type Err = ParseTargetError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let triple = s.trim();
+        let parts: Vec<&str> = triple.split('-').collect();
+
+        if parts.len() < 2 || parts.iter().any(|part| part.is_empty()) {
+            return Err(ParseTargetError);
+        }
+
+        // Android triples also contain "linux", e.g. aarch64-linux-android
+        let system = if parts[1..].iter().any(|part| part.starts_with("android")) {
+            System::Android
+        } else {
+            parts[1..]
+                .iter()
+                .filter_map(|part| parse_system(part))
+                .next()
+                .ok_or(ParseTargetError)?
+        };
+
+        Ok(Target {
+            triple: triple.to_string(),
+            arch: parts[0].to_string(),
+            system,
+        })
+    }
+}
+
+impl fmt::Display for System {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Values of CMAKE_SYSTEM_NAME
+        let name = match self {
+            System::Linux => "Linux",
+            System::Android => "Android",
+            System::Windows => "Windows",
+            System::Darwin => "Darwin",
+            System::FreeBSD => "FreeBSD",
+            System::Generic => "Generic",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+fn parse_system(part: &str) -> Option<System> {
+    match part {
+        "linux" => Some(System::Linux),
+        "windows" | "mingw32" | "w64" => Some(System::Windows),
+        "darwin" | "apple" | "macos" => Some(System::Darwin),
+        "freebsd" => Some(System::FreeBSD),
+        "none" | "elf" | "eabi" | "eabihf" => Some(System::Generic),
+        _ => None,
+    }
+}
+
+// Maps triple architecture names to the values of std::env::consts::ARCH
+fn normalize_arch(arch: &str) -> &str {
+    match arch {
+        "i386" | "i486" | "i586" | "i686" => "x86",
+        "arm64" => "aarch64",
+        "amd64" => "x86_64",
+        arch if arch.starts_with("armv") || arch == "armhf" || arch == "armel" => "arm",
+        arch => arch,
+    }
+}
+
+pub fn toolchain_file(target: &Target, sysroot: Option<&Path>, config: &TargetConfig) -> String {
+    let mut toolchain = String::new();
+
+    toolchain += &generator::set_var("CMAKE_SYSTEM_NAME", &target.system().to_string());
+    toolchain += &generator::set_var("CMAKE_SYSTEM_PROCESSOR", target.arch());
+    toolchain.push('\n');
+
+    let cc = config.cc.clone().unwrap_or_else(|| format!("{}-gcc", target.triple()));
+    let cxx = config.cxx.clone().unwrap_or_else(|| format!("{}-g++", target.triple()));
+
+    toolchain += &generator::set_var("CMAKE_C_COMPILER", &generator::path(&cc));
+    toolchain += &generator::set_var("CMAKE_CXX_COMPILER", &generator::path(&cxx));
+    toolchain.push('\n');
+
+    // Bare metal toolchains can't link an executable without a linker script, so cmake checks
+    // the compilers by building a static library instead
+    if target.system() == System::Generic {
+        toolchain += &generator::set_var("CMAKE_TRY_COMPILE_TARGET_TYPE", "STATIC_LIBRARY");
+        toolchain.push('\n');
+    }
+
+    if let Some(sysroot) = sysroot {
+        toolchain += &generator::set_var("CMAKE_SYSROOT", &generator::path(&sysroot.to_string_lossy()));
+    }
+
+    if let Some(root_path) = target.find_root_path(sysroot) {
+        toolchain += &generator::set_var("CMAKE_FIND_ROOT_PATH", &generator::path(&root_path.to_string_lossy()));
+    }
+
+    // Search programs on the host, libraries and headers in the target environment
+    toolchain += &generator::set_var("CMAKE_FIND_ROOT_PATH_MODE_PROGRAM", "NEVER");
+    toolchain += &generator::set_var("CMAKE_FIND_ROOT_PATH_MODE_LIBRARY", "ONLY");
+    toolchain += &generator::set_var("CMAKE_FIND_ROOT_PATH_MODE_INCLUDE", "ONLY");
+    toolchain += &generator::set_var("CMAKE_FIND_ROOT_PATH_MODE_PACKAGE", "ONLY");
+
+    toolchain
+}
+
+#[derive(Debug)]
+pub struct ParseTargetError;
+
+impl fmt::Display for ParseTargetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Given string is not a target triple")
+    }
+}
+
+impl Error for ParseTargetError {
+    fn description(&self) -> &str {
+        "Given string is not a target triple"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let target: Target = "aarch64-linux-gnu".parse().unwrap();
+        assert_eq!(target.arch(), "aarch64");
+        assert_eq!(target.system(), System::Linux);
+        assert_eq!(target.to_string(), "aarch64-linux-gnu");
+
+        let target: Target = "armv7-unknown-linux-gnueabihf".parse().unwrap();
+        assert_eq!(target.arch(), "armv7");
+        assert_eq!(target.system(), System::Linux);
+
+        let target: Target = "x86_64-w64-mingw32".parse().unwrap();
+        assert_eq!(target.system(), System::Windows);
+
+        let target: Target = "arm-none-eabi".parse().unwrap();
+        assert_eq!(target.system(), System::Generic);
+
+        let target: Target = "aarch64-linux-android21".parse().unwrap();
+        assert_eq!(target.system(), System::Android);
+
+        assert!("aarch64".parse::<Target>().is_err());
+        assert!("aarch64--gnu".parse::<Target>().is_err());
+        assert!("foo-bar-baz".parse::<Target>().is_err());
+    }
+
+    #[test]
+    fn runner_var() {
+        let target: Target = "aarch64-linux-gnu".parse().unwrap();
+        assert_eq!(target.runner_var(), "CBAKE_TARGET_AARCH64_LINUX_GNU_RUNNER");
+    }
+
+    #[test]
+    fn toolchain() {
+        let target: Target = "aarch64-linux-gnu".parse().unwrap();
+        let toolchain = toolchain_file(&target, Some(Path::new("/opt/sysroot")), &TargetConfig::default());
+
+        assert!(toolchain.contains("set(CMAKE_SYSTEM_NAME Linux)\n"));
+        assert!(toolchain.contains("set(CMAKE_SYSTEM_PROCESSOR aarch64)\n"));
+        assert!(toolchain.contains("set(CMAKE_C_COMPILER aarch64-linux-gnu-gcc)\n"));
+        assert!(toolchain.contains("set(CMAKE_CXX_COMPILER aarch64-linux-gnu-g++)\n"));
+        assert!(toolchain.contains("set(CMAKE_SYSROOT /opt/sysroot)\n"));
+        assert!(toolchain.contains("set(CMAKE_FIND_ROOT_PATH /opt/sysroot)\n"));
+
+        assert!(!toolchain.contains("CMAKE_TRY_COMPILE_TARGET_TYPE"));
+
+        let toolchain = toolchain_file(&target, Some(Path::new("/opt/my sysroot;x")), &TargetConfig::default());
+        assert!(toolchain.contains("set(CMAKE_SYSROOT \"/opt/my sysroot\\;x\")\n"));
+        assert!(toolchain.contains("set(CMAKE_FIND_ROOT_PATH \"/opt/my sysroot\\;x\")\n"));
+    }
+
+    #[test]
+    fn bare_metal_and_custom_compilers() {
+        let target: Target = "arm-none-eabi".parse().unwrap();
+        let toolchain = toolchain_file(&target, None, &TargetConfig::default());
+
+        assert!(toolchain.contains("set(CMAKE_SYSTEM_NAME Generic)\n"));
+        assert!(toolchain.contains("set(CMAKE_C_COMPILER arm-none-eabi-gcc)\n"));
+        assert!(toolchain.contains("set(CMAKE_TRY_COMPILE_TARGET_TYPE STATIC_LIBRARY)\n"));
+
+        let target: Target = "aarch64-linux-android".parse().unwrap();
+        let ndk = TargetConfig {
+            cc: Some(String::from("aarch64-linux-android21-clang")),
+            cxx: Some(String::from("/opt/android ndk/bin/aarch64-linux-android21-clang++")),
+        };
+        let toolchain = toolchain_file(&target, None, &ndk);
+
+        assert!(toolchain.contains("set(CMAKE_C_COMPILER aarch64-linux-android21-clang)\n"));
+        assert!(toolchain.contains("set(CMAKE_CXX_COMPILER \"/opt/android ndk/bin/aarch64-linux-android21-clang++\")\n"));
+        assert!(!toolchain.contains("CMAKE_TRY_COMPILE_TARGET_TYPE"));
+    }
+}
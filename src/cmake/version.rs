@@ -1,34 +1,72 @@
+use std::cmp::Ordering;
 use std::error::Error;
 use std::fmt;
 
 use std::str::FromStr;
 
 // Represents CMake version number
-#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct Version {
-    major: u8,
-    minor: u8,
-    revision: Option<u8>,
+    major: u32,
+    minor: u32,
+    revision: Option<u32>,
+    // Number of a release candidate like 3.28.0-rc2, it comes before the 3.28.0 release
+    release_candidate: Option<u32>,
 }
 
 impl Version {
-    pub fn new(major: u8, minor: u8) -> Self {
+    pub fn new(major: u32, minor: u32) -> Self {
         Version {
             major,
             minor,
             revision: None,
+            release_candidate: None,
         }
     }
 
-    pub fn with_revision(major: u8, minor: u8, revision: u8) -> Self {
+    pub fn with_revision(major: u32, minor: u32, revision: u32) -> Self {
         let revision = if revision == 0 { None } else { Some(revision) };
 
         Version {
             major,
             minor,
             revision,
+            release_candidate: None,
         }
     }
+
+    pub fn release_candidate(self, number: u32) -> Self {
+        Version {
+            release_candidate: Some(number),
+            ..self
+        }
+    }
+
+    pub fn is_pre_release(&self) -> bool {
+        self.release_candidate.is_some()
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let key = |ver: &Version| {
+            (
+                ver.major,
+                ver.minor,
+                ver.revision.unwrap_or(0),
+                ver.release_candidate.is_none(),
+                ver.release_candidate.unwrap_or(0),
+            )
+        };
+
+        key(self).cmp(&key(other))
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
 }
 
 impl fmt::Display for Version {
@@ -39,6 +77,14 @@ impl fmt::Display for Version {
             write!(f, ".{}", revision)?;
         }
 
+        if let Some(number) = self.release_candidate {
+            // Written the way cmake --version prints it
+            if self.revision.is_none() {
+                write!(f, ".0")?;
+            }
+            write!(f, "-rc{}", number)?;
+        }
+
         Ok(())
     }
 }
@@ -46,26 +92,42 @@ impl fmt::Display for Version {
 impl FromStr for Version {
     type Err = ParseVersionError;
 
+    // Accepts versions like 3.16, 3.16.3, 3.28.0-rc2 or 3.10.2.20180612. Release candidates are kept so
+    // they don't satisfy a requirement of the final release, vendor suffixes and components after the
+    // revision are dropped
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let nums: Vec<&str> = s.trim().split(".").collect();
+        let s = s.trim();
+        let numbers_end = s
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(s.len());
+        let (numbers, suffix) = s.split_at(numbers_end);
 
-        if !(nums.len() > 1 && nums.len() < 4) {
+        if suffix.contains(char::is_whitespace) {
             return Err(ParseVersionError);
         }
 
-        let major: u8 = nums[0].parse()?;
-        let minor: u8 = nums[1].parse()?;
-        let mut revision: Option<u8> = None;
+        let nums: Vec<&str> = numbers.trim_end_matches('.').split('.').collect();
 
-        if let Some(rev) = nums.get(2) {
-            revision = Some(rev.parse()?);
+        if nums.len() < 2 {
+            return Err(ParseVersionError);
         }
 
-        Ok(Version {
-            major,
-            minor,
-            revision,
-        })
+        let major: u32 = nums[0].parse()?;
+        let minor: u32 = nums[1].parse()?;
+        let revision = match nums.get(2) {
+            Some(rev) => rev.parse()?,
+            None => 0,
+        };
+
+        let version = Version::with_revision(major, minor, revision);
+
+        match suffix.strip_prefix("-rc") {
+            Some(number) => {
+                let number = number.split(|c: char| !c.is_ascii_digit()).next().unwrap_or("");
+                Ok(version.release_candidate(number.parse().unwrap_or(0)))
+            }
+            None => Ok(version),
+        }
     }
 }
 
@@ -74,7 +136,7 @@ pub struct ParseVersionError;
 
 impl fmt::Display for ParseVersionError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.description())
+        write!(f, "Given string is not a cmake version")
     }
 }
 
@@ -126,4 +188,45 @@ mod tests {
 
         assert!(ver1 < ver2 && ver2 == ver3 && ver3 < ver4 && ver4 < ver5 && ver5 <= ver6);
     }
+
+    #[test]
+    fn real_world_versions() {
+        let versions = [
+            ("2.8.12.2", Version::with_revision(2, 8, 12)),
+            ("3.5.1", Version::with_revision(3, 5, 1)),
+            ("3.16.3\n", Version::with_revision(3, 16, 3)),
+            ("3.16.0", Version::new(3, 16)),
+            ("3.28.0-rc2", Version::new(3, 28).release_candidate(2)),
+            ("3.22.1-msvc1", Version::with_revision(3, 22, 1)),
+            ("3.10.2.20180612", Version::with_revision(3, 10, 2)),
+            ("3.29.20240311-gabcdef0", Version::with_revision(3, 29, 20240311)),
+            ("3.27.7+dfsg", Version::with_revision(3, 27, 7)),
+            ("4.0", Version::new(4, 0)),
+            ("300.1000.70000", Version::with_revision(300, 1000, 70000)),
+        ];
+
+        for (string, version) in versions.iter() {
+            assert_eq!(string.parse::<Version>().unwrap(), *version, "{}", string);
+        }
+
+        for invalid in ["", "3", "3.", "version", "3..1", "a.b", "3.16 foo", "99999999999.1"].iter() {
+            assert!(invalid.parse::<Version>().is_err(), "{}", invalid);
+        }
+
+        assert!("3.28.0-rc2".parse::<Version>().unwrap() > "3.27.9".parse::<Version>().unwrap());
+    }
+
+    #[test]
+    fn release_candidates() {
+        let rc1 = "3.28.0-rc1".parse::<Version>().unwrap();
+        let rc2 = "3.28.0-rc2".parse::<Version>().unwrap();
+        let release = Version::new(3, 28);
+
+        assert!(rc2.is_pre_release() && !release.is_pre_release());
+        assert_ne!(rc2, release);
+        assert!(rc1 < rc2 && rc2 < release && rc2 < Version::with_revision(3, 28, 1));
+        assert!(rc2 > Version::with_revision(3, 27, 9));
+        assert_eq!(rc2.to_string(), "3.28.0-rc2");
+        assert_eq!("3.29.1-rc".parse::<Version>().unwrap().to_string(), "3.29.1-rc0");
+    }
 }
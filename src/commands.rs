@@ -0,0 +1,1235 @@
+/// This module implements the cbake commands. Every command works on the project at the given path
+use crate::cmake::{
+    self,
+    generator::TargetSettings,
+    toolchain::{self, System, Target},
+};
+use crate::error::{self, ErrorCode, ExecutionError, Failure, FatalError};
+use crate::manifest::{self, Manifest, ManifestError, Profile, ProjectKind, TargetConfig};
+use crate::message::{self, Message};
+use crate::project_layout::{self, ProjectLayout};
+use crate::sanitizer::{self, SanitizerSet};
+use crate::warnings::{WarningLevel, WarningPolicy};
+use crate::{coverage, diagnostics, doctor, project_name, templates, watch};
+
+use std::{
+    error::Error,
+    fmt,
+    io::{BufRead, BufReader, Read},
+    path::{Path, PathBuf},
+    process::{Child, Command, ExitStatus, Stdio},
+};
+
+pub type ExecutionResult = Result<(), ExecutionError>;
+
+pub const COMPILE_COMMANDS_FILE: &str = "compile_commands.json";
+
+pub const INSTALL_SECTION_BEGIN: &str =
+    "#-------- Warning: This section will be overwritten by cbake install and cbake package. Don't change it manually --------";
+pub const INSTALL_SECTION_END: &str = "#-------- End of the install section --------";
+
+// Same as in the manifest of a new project
+const DEFAULT_PACKAGE_VERSION: &str = "0.1.0";
+
+// Public headers of a library, installed along with it
+const LIBRARY_INCLUDE_DIR: &str = "include";
+
+// Emits the final status of a command
+pub fn report_result(result: &ExecutionResult) {
+    match result {
+        Ok(()) => message::emit(Message::Success),
+        Err(ExecutionError::Fatal(e)) => message::emit(Message::Error { error: e }),
+        Err(ExecutionError::Failure(_)) => message::emit(Message::Failure),
+    }
+}
+
+pub fn init() -> ExecutionResult {
+    Err(error::not_implemented_err("init").into())
+}
+
+// The project is named after its directory unless another name is given
+pub fn new(project_path: &Path, kind: ProjectKind, name: Option<&str>) -> ExecutionResult {
+    let project_name = match name {
+        Some(name) => {
+            project_name::validate(name).map_err(error::project_name_err)?;
+            name.to_string()
+        }
+        None => parse_project_name(project_path)?,
+    };
+
+    let cmake_ver = cmake::exec::version().map_err(error::version_err)?;
+
+    // The directory is shown when its name isn't the project name
+    let dir_name = project_path.file_name().map(|name| name.to_string_lossy());
+    let details = format!("in {}", project_path.display());
+
+    message::emit(Message::Status {
+        action: "Creating",
+        subject: &project_name,
+        details: if dir_name.as_deref() == Some(project_name.as_str()) { None } else { Some(&details) },
+    });
+
+    let mut layout = project_layout::Simple::new(project_path.to_path_buf());
+    let target = project_name::target_name(&project_name);
+
+    layout.generate().map_err(error::layout_gen_err)?;
+
+    if kind == ProjectKind::Library {
+        generate_library_sources(&mut layout, &target).map_err(error::layout_gen_err)?;
+    }
+
+    let source_list = layout.collect_sources().map_err(error::collect_sources_err)?;
+
+    let mut builder = cmake::Builder::new(project_name.clone(), cmake::generator::from_version(cmake_ver))
+        .version(String::from(DEFAULT_PACKAGE_VERSION))
+        .default_build_type(String::from("Debug"))
+        .cpp_standard(11)
+        .enable_testing()
+        .sources(source_list);
+
+    if kind == ProjectKind::Library {
+        builder = builder
+            .include_dirs(vec![String::from(LIBRARY_INCLUDE_DIR)])
+            .library(target);
+    }
+
+    let cmake_lists = builder.build();
+
+    layout.write_file("CMakeLists.txt", cmake_lists.as_bytes()).map_err(error::cmake_write_err)?;
+    layout
+        .write_file(manifest::MANIFEST_FILE, Manifest::new_project(&project_name, kind).as_bytes())
+        .map_err(error::manifest_write_err)?;
+
+    let project_path = layout.get_project_path();
+    let build_dir = layout.get_build_path();
+
+    configure(cmake::exec::InitExtBuilder::new(), &project_path, &build_dir)?;
+
+    Ok(())
+}
+
+// A library gets a public header in the include directory and a source instead of main.cpp.
+// Both are named after the target, so their names are plain ASCII
+fn generate_library_sources(layout: &mut dyn ProjectLayout, target: &str) -> std::io::Result<()> {
+    let namespace = templates::to_identifier(target);
+    let header = format!("{}.h", target);
+
+    layout.remove_file("main.cpp")?;
+    layout.create_dir(LIBRARY_INCLUDE_DIR)?;
+
+    layout.write_file(
+        &format!("{}/{}", LIBRARY_INCLUDE_DIR, header),
+        templates::library_header(&namespace, &header).as_bytes(),
+    )?;
+    layout.write_file(
+        &format!("{}.cpp", target),
+        templates::library_source(&namespace, &header).as_bytes(),
+    )
+}
+
+// The prefix must be absolute, cmake would resolve a relative one against the build directory
+pub fn install(project_path: &Path, options: &BuildOptions, prefix: Option<&Path>) -> ExecutionResult {
+    let mut layout = project_layout::Simple::new(project_path.to_path_buf());
+
+    let manifest = load_manifest(&layout)?;
+    let project_name = manifest_project_name(&manifest, &layout.get_project_path())?;
+    let profile = select_profile(&manifest, &options.profile)?;
+    let cmake_version = cmake::exec::version().map_err(error::version_err)?;
+
+    update_install_section(&mut layout, &manifest, &project_name, cmake_version)?;
+
+    // Success is reported once the whole command is done
+    build(project_path, options)?;
+
+    let prefix_details = prefix.map(|prefix| prefix.to_string_lossy().to_string());
+
+    message::emit(Message::Status {
+        action: "Installing",
+        subject: &project_name,
+        details: prefix_details.as_deref(),
+    });
+
+    cmake::exec::install(
+        &build_path(&layout, options),
+        &profile.build_type,
+        prefix,
+        cmake_version,
+        &mut |line| message::tool_output(line),
+    )
+    .map_err(error::install_err)?;
+
+    Ok(())
+}
+
+pub fn package(project_path: &Path, options: &BuildOptions) -> ExecutionResult {
+    let mut layout = project_layout::Simple::new(project_path.to_path_buf());
+
+    let manifest = load_manifest(&layout)?;
+    let project_name = manifest_project_name(&manifest, &layout.get_project_path())?;
+    let profile = select_profile(&manifest, &options.profile)?;
+    let cmake_version = cmake::exec::version().map_err(error::version_err)?;
+
+    // Debian packages can't be made without a maintainer
+    if manifest.package.maintainer.is_none() {
+        return Err(error::package_err(MissingMaintainer).into());
+    }
+
+    update_install_section(&mut layout, &manifest, &project_name, cmake_version)?;
+
+    // Success is reported once the whole command is done
+    build(project_path, options)?;
+
+    message::emit(Message::Status {
+        action: "Packaging",
+        subject: &project_name,
+        details: Some(&profile.name),
+    });
+
+    let output = cmake::exec::package(&build_path(&layout, options), &profile.build_type, &mut |line| {
+        message::tool_output(line)
+    })
+    .map_err(error::package_err)?;
+
+    for file in output.packages.iter() {
+        message::emit(Message::PackageCreated { file });
+    }
+
+    if output.success {
+        Ok(())
+    } else {
+        Err(Failure.into())
+    }
+}
+
+// Writes install rules into CMakeLists.txt. CPack settings are added once the manifest has
+// a maintainer, so the section doesn't change between cbake install and cbake package
+fn update_install_section(
+    layout: &mut dyn ProjectLayout,
+    manifest: &Manifest,
+    project_name: &str,
+    cmake_version: cmake::Version,
+) -> Result<(), FatalError> {
+    let generator = cmake::generator::from_version(cmake_version);
+
+    let include_dir = if layout.get_project_path().join("include").is_dir() {
+        Some("include")
+    } else {
+        None
+    };
+
+    let version = manifest.package.version.as_deref().unwrap_or(DEFAULT_PACKAGE_VERSION);
+
+    // Libraries are installed by the package config section made by cbake new
+    let mut section = match manifest.package.kind {
+        ProjectKind::Executable => generator.install_rules(include_dir),
+        ProjectKind::Library if manifest.package.pkg_config => generator.pkg_config(&cmake::generator::PkgConfig {
+            name: project_name,
+            version,
+            description: manifest.package.description.as_deref(),
+            link_libs: &manifest.build.link_libraries,
+        }),
+        ProjectKind::Library => String::new(),
+    };
+
+    if let Some(maintainer) = &manifest.package.maintainer {
+        section.push('\n');
+        section += &cmake::generator::cpack(
+            &project_name::target_name(project_name),
+            version,
+            maintainer,
+            manifest.package.description.as_deref(),
+        );
+    }
+
+    let mut cmake_lists = String::new();
+    layout
+        .open_file("CMakeLists.txt")
+        .and_then(|mut file| file.read_to_string(&mut cmake_lists))
+        .map_err(error::cmake_read_err)?;
+
+    replace_section(&mut cmake_lists, INSTALL_SECTION_BEGIN, INSTALL_SECTION_END, &section);
+
+    layout.write_file("CMakeLists.txt", cmake_lists.as_bytes()).map_err(error::cmake_write_err)
+}
+
+pub fn add_class(project_path: &Path, class_name: &str, namespace: Option<&str>, pragma_once: bool) -> ExecutionResult {
+    let mut layout = project_layout::Simple::new(project_path.to_path_buf());
+
+    let class = templates::ClassTemplate::new(class_name, namespace)
+        .map_err(error::add_source_err)?
+        .pragma_once(pragma_once);
+
+    // Fail before creating anything if the project is not there
+    layout.open_file("CMakeLists.txt").map_err(error::cmake_read_err)?;
+
+    let manifest = load_manifest(&layout)?;
+    let (header, source) = layout.class_files(class_name, manifest.package.kind);
+
+    for file in [&header, &source].iter() {
+        if layout.open_file(file).is_ok() {
+            let exists = std::io::Error::new(std::io::ErrorKind::AlreadyExists, format!("{} already exists", file));
+            return Err(error::add_source_err(exists).into());
+        }
+    }
+
+    let header_include = Path::new(&header)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| header.clone());
+
+    // The include directory of a library may have been removed since cbake new
+    if let Some(header_dir) = Path::new(&header).parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        if !layout.get_project_path().join(header_dir).is_dir() {
+            layout
+                .create_dir(&header_dir.to_string_lossy())
+                .map_err(error::add_source_err)?;
+        }
+    }
+
+    layout
+        .write_file(&header, class.header(&header).as_bytes())
+        .map_err(error::add_source_err)?;
+    layout
+        .write_file(&source, class.source(&header_include).as_bytes())
+        .map_err(error::add_source_err)?;
+
+    for file in [&header, &source].iter() {
+        message::emit(Message::Status {
+            action: "Created",
+            subject: file,
+            details: None,
+        });
+    }
+
+    update_source_list(&mut layout, &manifest)?;
+
+    Ok(())
+}
+
+pub fn rm(project_path: &Path, name: &str) -> ExecutionResult {
+    let mut layout = project_layout::Simple::new(project_path.to_path_buf());
+
+    let mut sources: Vec<String> = layout
+        .collect_sources()
+        .map_err(error::collect_sources_err)?
+        .into_iter()
+        .filter(|source| Path::new(source).file_stem().is_some_and(|stem| stem == name))
+        .collect();
+
+    // Public headers of libraries aren't sources but belong to the class
+    let manifest = load_manifest(&layout)?;
+    let (header, _) = layout.class_files(name, manifest.package.kind);
+    if !sources.contains(&header) && layout.open_file(&header).is_ok() {
+        sources.push(header);
+    }
+    sources.sort();
+
+    if sources.is_empty() {
+        let not_found = std::io::Error::new(std::io::ErrorKind::NotFound, format!("no sources named {}", name));
+        return Err(error::remove_source_err(not_found).into());
+    }
+
+    for source in sources.iter() {
+        layout.remove_file(source).map_err(error::remove_source_err)?;
+
+        message::emit(Message::Status {
+            action: "Removed",
+            subject: source,
+            details: None,
+        });
+    }
+
+    update_source_list(&mut layout, &manifest)?;
+
+    Ok(())
+}
+
+pub fn watch(project_path: &Path, action: &str, options: &BuildOptions) -> ExecutionResult {
+    let layout = project_layout::Simple::new(project_path.to_path_buf());
+
+    if action == "run" {
+        ensure_executable(project_path)?;
+    }
+
+    let runner = match &options.target {
+        Some(target) if action == "run" && !target.is_host() => find_runner(target)?,
+        _ => Vec::new(),
+    };
+
+    let mut program: Option<(String, Child)> = None;
+
+    loop {
+        // A stale program is stopped before the build replaces its executable
+        if let Some((_, mut child)) = program.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+
+        let result = match action {
+            "run" => build(project_path, options).and_then(|()| {
+                message::emit(Message::Success);
+
+                let (project_name, mut command) = run_command(project_path, options, &runner)?;
+
+                message::emit(Message::Status {
+                    action: "Running",
+                    subject: &project_name,
+                    details: None,
+                });
+
+                let child = command.spawn().map_err(error::run_err)?;
+                program = Some((project_name, child));
+
+                Ok(())
+            }),
+            "test" => test(project_path, options),
+            _ => build(project_path, options),
+        };
+
+        // The program reports its own success
+        if result.is_err() || program.is_none() {
+            report_result(&result);
+        }
+
+        // Taken after the build, which rewrites CMakeLists.txt
+        let mut snapshot = watch::Snapshot::take(&layout).map_err(error::collect_sources_err)?;
+        let watched_files = format!("{} files", snapshot.file_count());
+
+        message::emit(Message::Status {
+            action: "Watching",
+            subject: &layout.get_project_path().to_string_lossy(),
+            details: Some(&watched_files),
+        });
+
+        let mut reap_program = || {
+            let exited = match &mut program {
+                Some((_, child)) => child.try_wait().ok().flatten(),
+                None => None,
+            };
+
+            if let (Some(status), Some((project_name, _))) = (exited, program.take()) {
+                message::emit(Message::Status {
+                    action: "Exited",
+                    subject: &project_name,
+                    details: Some(&status.to_string()),
+                });
+            }
+        };
+
+        let changed = watch::wait_for_changes(&layout, &mut snapshot, &mut reap_program)
+            .map_err(error::collect_sources_err)?;
+
+        let changed: Vec<String> = changed
+            .iter()
+            .map(|file| watch::display_path(file, &layout.get_project_path()))
+            .collect();
+
+        message::emit(Message::Status {
+            action: "Changed",
+            subject: &changed.join(", "),
+            details: None,
+        });
+    }
+}
+
+pub fn doctor(project_path: &Path) -> ExecutionResult {
+    let layout = project_layout::Simple::new(project_path.to_path_buf());
+
+    message::emit(Message::Status {
+        action: "Checking",
+        subject: "environment",
+        details: None,
+    });
+
+    let mut checks = doctor::tool_checks();
+    checks.iter().for_each(|check| message::emit(Message::Check { check }));
+
+    message::emit(Message::Status {
+        action: "Checking",
+        subject: "project",
+        details: Some(&layout.get_project_path().to_string_lossy()),
+    });
+
+    let cmake_version = cmake::exec::version().ok();
+    let project_checks = doctor::project_checks(&layout.get_project_path(), &layout.get_build_path(), cmake_version);
+    project_checks.iter().for_each(|check| message::emit(Message::Check { check }));
+
+    checks.extend(project_checks);
+
+    if checks.iter().any(|check| check.status == doctor::CheckStatus::Error) {
+        Err(Failure.into())
+    } else {
+        Ok(())
+    }
+}
+
+pub fn explain(code: &str) -> ExecutionResult {
+    let code: ErrorCode = code.parse().map_err(error::explain_err)?;
+
+    message::emit(Message::Explanation {
+        code: &code.to_string(),
+        explanation: code.explanation(),
+        exit_code: code.exit_code(),
+    });
+
+    Ok(())
+}
+
+pub fn build(project_path: &Path, options: &BuildOptions) -> ExecutionResult {
+    let mut layout = project_layout::Simple::new(project_path.to_path_buf());
+    let manifest = load_manifest(&layout)?;
+    let project_name = manifest_project_name(&manifest, &layout.get_project_path())?;
+    let profile = select_profile(&manifest, &options.profile)?;
+
+    let mut build_details = profile.name.clone();
+    if let Some(target) = &options.target {
+        build_details = format!("{}, {}", build_details, target);
+    }
+    if !options.sanitizers.is_empty() {
+        build_details = format!("{}, sanitize={}", build_details, options.sanitizers);
+    }
+    if options.coverage {
+        build_details = format!("{}, coverage", build_details);
+    }
+
+    message::emit(Message::Status {
+        action: "Building",
+        subject: &project_name,
+        details: Some(&build_details),
+    });
+
+    update_source_list(&mut layout, &manifest)?;
+    // Command line options win over the manifest
+    let warnings = WarningPolicy {
+        level: options.warnings.unwrap_or(manifest.build.warnings),
+        deny: options.deny_warnings || manifest.build.deny_warnings,
+    };
+    let instrumented = !options.sanitizers.is_empty() || options.coverage;
+    update_settings_section(&mut layout, &manifest, &project_name, warnings, instrumented)?;
+
+    let build_dir = build_path(&layout, options);
+    let source_dir = layout.get_project_path();
+
+    let output_dir = format!("./{}", profile.output_dir);
+    let mut init = cmake::exec::InitExtBuilder::new()
+        .set_var("CMAKE_BUILD_TYPE", &profile.build_type)
+        .set_var(cmake::builder::PROFILE_VAR, &profile.name)
+        .set_var("CMAKE_RUNTIME_OUTPUT_DIRECTORY", &output_dir)
+        .set_var("CMAKE_ARCHIVE_OUTPUT_DIRECTORY", &output_dir)
+        .set_var("CMAKE_LIBRARY_OUTPUT_DIRECTORY", &output_dir);
+
+    // Sanitized and instrumented builds have build directories of their own, so the flags never
+    // reach other builds. CMAKE_<LANG>_FLAGS are left to the user
+    if instrumented {
+        let coverage_flags = if options.coverage { coverage::COVERAGE_FLAGS } else { "" };
+        let compile_flags = join_flags(&[options.sanitizers.compile_flags(), coverage_flags.to_string()]);
+        let link_flags = join_flags(&[options.sanitizers.link_flags(), coverage_flags.to_string()]);
+
+        init = init
+            .set_var(cmake::builder::COMPILE_FLAGS_VAR, &compile_flags)
+            .set_var(cmake::builder::LINK_FLAGS_VAR, &link_flags);
+    }
+
+    if let Some(target) = &options.target {
+        let toolchain_path =
+            write_toolchain_file(&build_dir, target, &manifest.target(target.triple())).map_err(error::toolchain_write_err)?;
+        init = init.set_var("CMAKE_TOOLCHAIN_FILE", &toolchain_path.to_string_lossy());
+    }
+
+    configure(init, &source_dir, &build_dir)?;
+
+    let mut echo_output = |line: &str| {
+        if options.verbose {
+            message::tool_output(line);
+        }
+    };
+
+    let output = cmake::exec::build(&build_dir, &mut echo_output)
+        .map_err(error::build_command_err)?;
+
+    let diagnostics = diagnostics::parse(&output.lines, &build_dir);
+    let summary = diagnostics::Summary::new(&diagnostics);
+
+    // Diagnostics are always reported to JSON consumers, humans see either them or the raw output
+    if !options.verbose || message::is_json() {
+        for diagnostic in &diagnostics {
+            message::emit(Message::Diagnostic {
+                diagnostic,
+                project_root: &source_dir,
+            });
+        }
+    }
+
+    // Failures like linker errors are not recognized as diagnostics, the raw output is the only clue
+    if !options.verbose && !output.success && summary.errors == 0 {
+        message::emit(Message::BuildOutput { lines: &output.lines });
+    }
+
+    message::emit(Message::BuildFinished {
+        success: output.success,
+        summary,
+    });
+
+    if output.success {
+        let artifact = match manifest.package.kind {
+            ProjectKind::Executable => executable_path(&build_dir, &profile, &project_name, options),
+            ProjectKind::Library => library_path(&build_dir, &profile, &project_name),
+        };
+
+        message::emit(Message::TargetBuilt {
+            name: &project_name,
+            artifact: &artifact,
+        });
+
+        Ok(())
+    } else {
+        Err(ExecutionError::from(Failure))
+    }
+}
+
+// Configures the build directory and exports its compile commands
+fn configure(init: cmake::exec::InitExtBuilder, source_dir: &Path, build_dir: &Path) -> Result<(), FatalError> {
+    message::emit(Message::ConfigureStarted { build_dir });
+
+    let result = init
+        .output_to_stderr(message::is_json())
+        .execute(source_dir, build_dir);
+
+    message::emit(Message::ConfigureFinished {
+        build_dir,
+        success: result.is_ok(),
+    });
+
+    result.map_err(error::init_err)?;
+    export_compile_commands(source_dir, build_dir).map_err(error::compile_commands_err)
+}
+
+// Artifacts are named after the target, not the project
+fn executable_path(build_dir: &Path, profile: &Profile, project_name: &str, options: &BuildOptions) -> PathBuf {
+    let mut executable_path = build_dir.join(&profile.output_dir).join(project_name::target_name(project_name));
+
+    if options.target.as_ref().is_some_and(|target| target.system() == System::Windows) {
+        executable_path.set_extension("exe");
+    }
+
+    executable_path
+}
+
+// Static library, the cmake default
+fn library_path(build_dir: &Path, profile: &Profile, project_name: &str) -> PathBuf {
+    let target = project_name::target_name(project_name);
+    let file_name = if cfg!(target_env = "msvc") {
+        format!("{}.lib", target)
+    } else {
+        format!("lib{}.a", target)
+    };
+
+    build_dir.join(&profile.output_dir).join(file_name)
+}
+
+fn ensure_executable(project_path: &Path) -> Result<(), FatalError> {
+    let layout = project_layout::Simple::new(project_path.to_path_buf());
+
+    if load_manifest(&layout)?.package.kind == ProjectKind::Library {
+        return Err(FatalError::with_help(
+            ErrorCode::RunExecutable,
+            Box::new(LibraryProject),
+            "Cannot run the project",
+            "Libraries are built with `cbake build` and tested with `cbake test`",
+        ));
+    }
+
+    Ok(())
+}
+
+pub fn run(project_path: &Path, options: &BuildOptions) -> ExecutionResult {
+    ensure_executable(project_path)?;
+
+    // Check before building to not waste time on a build which can't be run
+    let runner = match &options.target {
+        Some(target) if !target.is_host() => find_runner(target)?,
+        _ => Vec::new(),
+    };
+
+    build(project_path, options)?;
+    message::emit(Message::Success);
+
+    let (project_name, mut command) = run_command(project_path, options, &runner)?;
+
+    message::emit(Message::Status {
+        action: "Running",
+        subject: &project_name,
+        details: None,
+    });
+
+    let status = if options.sanitizers.is_empty() {
+        command.status().map_err(error::run_err)?
+    } else {
+        run_sanitized(command).map_err(error::run_err)?
+    };
+
+    // A program which exits with an error is reported like a failed build
+    if status.success() {
+        Ok(())
+    } else {
+        Err(Failure.into())
+    }
+}
+
+// Returns the project name and a command which runs the built executable.
+// Foreign targets are run with the runner, sanitized builds get the sanitizer runtime options
+fn run_command(project_path: &Path, options: &BuildOptions, runner: &[String]) -> Result<(String, Command), FatalError> {
+    let layout = project_layout::Simple::new(project_path.to_path_buf());
+    let manifest = load_manifest(&layout)?;
+    let project_name = manifest_project_name(&manifest, &layout.get_project_path())?;
+    let profile = select_profile(&manifest, &options.profile)?;
+
+    let executable_path = executable_path(&build_path(&layout, options), &profile, &project_name, options);
+
+    let mut command = match runner.split_first() {
+        Some((runner, runner_args)) => {
+            let mut command = Command::new(runner);
+            command.args(runner_args).arg(executable_path);
+            command
+        }
+        None => Command::new(executable_path),
+    };
+
+    for (var, options) in options.sanitizers.runtime_options() {
+        if std::env::var_os(var).is_none() {
+            command.env(var, options);
+        }
+    }
+
+    Ok((project_name, command))
+}
+
+// Runs the executable and summarizes sanitizer reports found in its stderr
+fn run_sanitized(mut command: Command) -> std::io::Result<ExitStatus> {
+    let mut child = command.stderr(Stdio::piped()).spawn()?;
+    let mut lines = Vec::new();
+
+    if let Some(stderr) = child.stderr.take() {
+        for line in BufReader::new(stderr).lines() {
+            let line = line?;
+            eprintln!("{}", line);
+            lines.push(line);
+        }
+    }
+
+    let status = child.wait()?;
+    print_sanitizer_reports(&lines);
+
+    Ok(status)
+}
+
+fn print_sanitizer_reports(output: &[String]) {
+    let reports: Vec<sanitizer::Report> = output.iter().filter_map(|line| sanitizer::parse_report(line)).collect();
+
+    message::emit(Message::SanitizerReports { reports: &reports });
+}
+
+pub fn test(project_path: &Path, options: &BuildOptions) -> ExecutionResult {
+    build(project_path, options)?;
+    message::emit(Message::Success);
+
+    let layout = project_layout::Simple::new(project_path.to_path_buf());
+    let manifest = load_manifest(&layout)?;
+    let project_name = manifest_project_name(&manifest, &layout.get_project_path())?;
+    let profile = select_profile(&manifest, &options.profile)?;
+    let build_dir = build_path(&layout, options);
+
+    message::emit(Message::Status {
+        action: "Testing",
+        subject: &project_name,
+        details: None,
+    });
+
+    if options.coverage {
+        coverage::reset_counters(&build_dir).map_err(error::coverage_err)?;
+    }
+
+    let envs: Vec<(&str, &str)> = options
+        .sanitizers
+        .runtime_options()
+        .into_iter()
+        .filter(|(var, _)| std::env::var_os(var).is_none())
+        .collect();
+
+    let mut report_test = |line: &str| {
+        message::tool_output(line);
+
+        if let Some(result) = cmake::exec::parse_test_result(line) {
+            message::emit(Message::TestResult {
+                name: &result.name,
+                success: result.success,
+                duration: result.duration,
+            });
+        }
+    };
+
+    let output = cmake::exec::test(&build_dir, &profile.build_type, &envs, &mut report_test)
+        .map_err(error::ctest_err)?;
+
+    if !options.sanitizers.is_empty() {
+        print_sanitizer_reports(&output.lines);
+    }
+
+    if options.coverage {
+        report_coverage(&layout, &build_dir, &project_name).map_err(error::coverage_err)?;
+    }
+
+    if output.success {
+        Ok(())
+    } else {
+        Err(ExecutionError::from(Failure))
+    }
+}
+
+// Prints a per-file summary and writes lcov.info and an HTML report into the build directory
+fn report_coverage(layout: &dyn ProjectLayout, build_dir: &Path, project_name: &str) -> std::io::Result<()> {
+    let project_root = layout.get_project_path().canonicalize()?;
+    let sources: Vec<PathBuf> = layout
+        .collect_sources()?
+        .iter()
+        .filter_map(|source| project_root.join(source).canonicalize().ok())
+        .collect();
+
+    let files = coverage::collect(&coverage::gcov_tool(build_dir), build_dir, &sources)?;
+
+    let covered: usize = files.iter().map(coverage::FileCoverage::covered_lines).sum();
+    let total: usize = files.iter().map(coverage::FileCoverage::executable_lines).sum();
+
+    let lcov_path = build_dir.join("lcov.info");
+    let html_dir = build_dir.join("html");
+    let html_path = html_dir.join("index.html");
+
+    std::fs::create_dir_all(&html_dir)?;
+    std::fs::write(&lcov_path, coverage::lcov_info(&files))?;
+    std::fs::write(&html_path, coverage::html_report(project_name, &project_root, &files))?;
+
+    let summary: Vec<message::CoverageFile> = files
+        .iter()
+        .map(|file| message::CoverageFile {
+            file: coverage::display_path(&project_root, &file.path),
+            covered: file.covered_lines(),
+            total: file.executable_lines(),
+            percent: file.percent(),
+        })
+        .collect();
+
+    message::emit(Message::Coverage {
+        files: &summary,
+        covered,
+        total,
+        percent: coverage::percent(covered, total),
+        lcov: &lcov_path,
+        html: &html_path,
+    });
+
+    Ok(())
+}
+
+// Removes the build directory along with the compile_commands.json which points into it
+pub fn clean(project_path: &Path) -> ExecutionResult {
+    let layout = project_layout::Simple::new(project_path.to_path_buf());
+
+    // Nothing is removed outside of a project
+    layout.open_file("CMakeLists.txt").map_err(error::cmake_read_err)?;
+
+    let project_root = layout.get_project_path();
+    let build_dir = layout.get_build_path();
+
+    message::emit(Message::Status {
+        action: "Cleaning",
+        subject: &build_dir.to_string_lossy(),
+        details: None,
+    });
+
+    if build_dir.exists() {
+        std::fs::remove_dir_all(&build_dir).map_err(error::dir_access_err)?;
+    }
+
+    let compile_commands = project_root.join(COMPILE_COMMANDS_FILE);
+    if compile_commands.symlink_metadata().is_ok() {
+        std::fs::remove_file(&compile_commands).map_err(error::dir_access_err)?;
+    }
+
+    Ok(())
+}
+
+// Options shared by commands which build a project
+#[derive(Debug, Clone)]
+pub struct BuildOptions {
+    pub profile: String,
+    pub target: Option<Target>,
+    pub sanitizers: SanitizerSet,
+    pub coverage: bool,
+    pub verbose: bool,
+    // Overrides the warning level of the manifest
+    pub warnings: Option<WarningLevel>,
+    pub deny_warnings: bool,
+}
+
+// A debug build for the host
+impl Default for BuildOptions {
+    fn default() -> Self {
+        BuildOptions {
+            profile: String::from("debug"),
+            target: None,
+            sanitizers: SanitizerSet::default(),
+            coverage: false,
+            verbose: false,
+            warnings: None,
+            deny_warnings: false,
+        }
+    }
+}
+
+// Projects created by older cbake versions have no manifest
+pub(crate) fn load_manifest(layout: &dyn ProjectLayout) -> Result<Manifest, FatalError> {
+    match layout.open_file(manifest::MANIFEST_FILE) {
+        Ok(file) => Manifest::from_reader(file).map_err(error::manifest_read_err),
+        Err(ref e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Manifest::default()),
+        Err(e) => Err(error::manifest_read_err(ManifestError::from(e))),
+    }
+}
+
+fn select_profile(manifest: &Manifest, profile_name: &str) -> Result<Profile, FatalError> {
+    manifest
+        .profile(profile_name)
+        .map_err(|e| error::profile_err(e, &manifest.profile_names()))
+}
+
+// The manifest name is used when present, so renaming the project directory doesn't break the build
+fn manifest_project_name(manifest: &Manifest, project_path: &Path) -> Result<String, FatalError> {
+    match &manifest.package.name {
+        Some(name) => {
+            project_name::validate(name).map_err(error::project_name_err)?;
+            Ok(name.clone())
+        }
+        None => parse_project_name(project_path),
+    }
+}
+
+// Cross-compiled, sanitized and instrumented builds get their own build directories next to Debug/ and Release/
+fn build_path(layout: &dyn ProjectLayout, options: &BuildOptions) -> PathBuf {
+    let mut build_path = layout.get_build_path();
+
+    if let Some(target) = &options.target {
+        build_path.push(target.triple());
+    }
+
+    if !options.sanitizers.is_empty() {
+        build_path.push(options.sanitizers.build_dir_name());
+    }
+
+    if options.coverage {
+        build_path.push("coverage");
+    }
+
+    build_path
+}
+
+// Makes compile_commands.json of the last configured build directory available in the project root.
+// A symlink follows the file when cmake regenerates it, platforms without symlinks get a copy
+fn export_compile_commands(project_dir: &Path, build_dir: &Path) -> std::io::Result<()> {
+    let source = build_dir.join(COMPILE_COMMANDS_FILE);
+    let destination = project_dir.join(COMPILE_COMMANDS_FILE);
+
+    // Not every cmake generator supports compile commands export
+    if !source.exists() {
+        return Ok(());
+    }
+
+    if destination.symlink_metadata().is_ok() {
+        std::fs::remove_file(&destination)?;
+    }
+
+    #[cfg(unix)]
+    std::os::unix::fs::symlink(source.canonicalize()?, &destination)?;
+
+    #[cfg(not(unix))]
+    std::fs::copy(&source, &destination).map(|_| ())?;
+
+    Ok(())
+}
+
+fn join_flags(flags: &[String]) -> String {
+    let flags: Vec<&str> = flags.iter().map(|f| f.trim()).filter(|f| !f.is_empty()).collect();
+    flags.join(" ")
+}
+
+// Writes a toolchain file for the target into the build directory. The sysroot may be given by CBAKE_SYSROOT
+fn write_toolchain_file(build_dir: &Path, target: &Target, config: &TargetConfig) -> std::io::Result<PathBuf> {
+    let sysroot = std::env::var_os("CBAKE_SYSROOT").map(PathBuf::from);
+    let toolchain_path = build_dir.join("toolchain.cmake");
+
+    std::fs::create_dir_all(build_dir)?;
+    std::fs::write(&toolchain_path, toolchain::toolchain_file(target, sysroot.as_deref(), config))?;
+
+    Ok(toolchain_path)
+}
+
+// Returns a command line used to run executables of a foreign target, e.g. qemu-user
+fn find_runner(target: &Target) -> Result<Vec<String>, FatalError> {
+    let runner_var = target.runner_var();
+    let runner: Vec<String> = std::env::var(&runner_var)
+        .unwrap_or_default()
+        .split_whitespace()
+        .map(String::from)
+        .collect();
+
+    if runner.is_empty() {
+        Err(FatalError::with_help(
+            ErrorCode::ForeignTarget,
+            Box::new(ForeignTarget),
+            format!("Cannot run an executable built for {} on this machine", target),
+            format!(
+                "Set {} to a runner command, e.g. `qemu-{} -L /usr/{}`",
+                runner_var,
+                target.arch(),
+                target.triple()
+            ),
+        ))
+    } else {
+        Ok(runner)
+    }
+}
+
+// The last component of the path, with characters which aren't allowed in project names replaced
+fn parse_project_name(project_path: &Path) -> Result<String, FatalError> {
+    let dir_name = project_path.file_name().ok_or(FatalError::with_help(
+        ErrorCode::ProjectName,
+        Box::new(InvalidPath),
+        "Cannot parse a project name",
+        "This command creates a new directory with project contents. The path must end with a non existing directory name",
+    ))?;
+
+    Ok(project_name::sanitize(&dir_name.to_string_lossy()))
+}
+
+// Rewrites the add_executable (or add_library) command of CMakeLists.txt with the current project sources
+fn update_source_list(layout: &mut dyn ProjectLayout, manifest: &Manifest) -> Result<(), FatalError> {
+    let target = project_name::target_name(&manifest_project_name(manifest, &layout.get_project_path())?);
+
+    let mut sources = layout.collect_sources().map_err(error::collect_sources_err)?;
+    sources.sort();
+
+    let mut cmake_file = layout.open_file("CMakeLists.txt").map_err(error::cmake_read_err)?;
+
+    let mut cmake_lists = String::new();
+    cmake_file.read_to_string(&mut cmake_lists).map_err(error::cmake_read_err)?;
+
+    let (command_name, command) = match manifest.package.kind {
+        ProjectKind::Executable => ("add_executable", cmake::generator::add_executable(&sources)),
+        ProjectKind::Library => ("add_library", cmake::generator::add_library(&sources)),
+    };
+
+    // The target follows a renamed project. CMakeLists.txt made by older versions names the
+    // target after the project and gets the variable defined above the command
+    let target_var = cmake::builder::TARGET_VAR;
+    let target_definition = cmake::generator::set_var(target_var, &target);
+    if !replace_command(&mut cmake_lists, &format!("set({} ", target_var), &target_definition) {
+        match cmake_lists.find(command_name) {
+            Some(position) => cmake_lists.insert_str(position, &target_definition),
+            None => {
+                if !cmake_lists.is_empty() && !cmake_lists.ends_with('\n') {
+                    cmake_lists.push('\n');
+                }
+                cmake_lists.push_str(&target_definition);
+            }
+        }
+    }
+
+    if !replace_command(&mut cmake_lists, command_name, &command) {
+        cmake_lists.push_str(&command);
+    }
+
+    layout.write_file("CMakeLists.txt", cmake_lists.as_bytes()).map_err(error::cmake_write_err)?;
+
+    Ok(())
+}
+
+// Writes compile and link settings of the manifest and its profiles along with the warning
+// flags into CMakeLists.txt. Projects which never had any settings are left untouched. Once
+// the project was built with sanitizers or coverage the section keeps applying their flags
+fn update_settings_section(
+    layout: &mut dyn ProjectLayout,
+    manifest: &Manifest,
+    project_name: &str,
+    warnings: WarningPolicy,
+    instrumented: bool,
+) -> Result<(), FatalError> {
+    let mut cmake_lists = String::new();
+    layout
+        .open_file("CMakeLists.txt")
+        .and_then(|mut file| file.read_to_string(&mut cmake_lists))
+        .map_err(error::cmake_read_err)?;
+
+    let build = &manifest.build;
+    let settings = TargetSettings {
+        definitions: build.definitions.clone(),
+        compile_options: build.options.clone(),
+        link_options: build.link_options.clone(),
+        link_libraries: build.link_libraries.clone(),
+    };
+
+    // The profile being built was resolved already, broken ones which aren't used only get a warning
+    let mut profile_settings = Vec::new();
+    for name in manifest.profile_names() {
+        let profile = match manifest.profile(&name) {
+            Ok(profile) => profile,
+            Err(e) => {
+                message::emit(Message::Warning {
+                    message: &format!("profile `{}` is skipped in CMakeLists.txt: {}", name, e),
+                });
+                continue;
+            }
+        };
+
+        profile_settings.push((
+            name,
+            TargetSettings {
+                definitions: profile.definitions,
+                compile_options: profile.flags,
+                link_options: profile.link_options,
+                link_libraries: profile.link_libraries,
+            },
+        ));
+    }
+
+    let instrumented = instrumented || cmake_lists.contains(cmake::builder::COMPILE_FLAGS_VAR);
+    let has_settings = !settings.is_empty()
+        || !warnings.is_empty()
+        || instrumented
+        || profile_settings.iter().any(|(_, settings)| !settings.is_empty());
+    if !has_settings && !cmake_lists.contains(cmake::builder::SETTINGS_SECTION_BEGIN) {
+        return Ok(());
+    }
+
+    // The section is written by the generator CMakeLists.txt was made with, so builds don't start
+    // `cmake --version`. Only hand-written files without cmake_minimum_required need the installed cmake
+    let cmake_version = match cmake::builder::min_version(&cmake_lists) {
+        Some(version) => version,
+        None => cmake::exec::version().map_err(error::version_err)?,
+    };
+    let mut builder = profile_settings.into_iter().fold(
+        cmake::Builder::new(project_name.to_string(), cmake::generator::from_version(cmake_version))
+            .target_settings(settings)
+            .warnings(warnings),
+        |builder, (name, settings)| builder.profile_settings(name, settings),
+    );
+
+    if instrumented {
+        builder = builder.instrumentation();
+    }
+
+    replace_section(
+        &mut cmake_lists,
+        cmake::builder::SETTINGS_SECTION_BEGIN,
+        cmake::builder::SETTINGS_SECTION_END,
+        &builder.settings_section(),
+    );
+
+    layout.write_file("CMakeLists.txt", cmake_lists.as_bytes()).map_err(error::cmake_write_err)
+}
+
+// Replaces the text between the marker lines, the section is appended if there is none
+fn replace_section(buf: &mut String, begin: &str, end: &str, new_section_text: &str) {
+    let section = format!("{}\n{}{}\n", begin, new_section_text, end);
+
+    let section_range = buf.find(begin).and_then(|start| {
+        buf[start..]
+            .find(end)
+            .map(|i| (start, start + i + end.len()))
+    });
+
+    match section_range {
+        Some((start, end)) => {
+            let end = if buf[end..].starts_with('\n') { end + 1 } else { end };
+            buf.replace_range(start..end, &section);
+        }
+        None => {
+            if !buf.ends_with('\n') {
+                buf.push('\n');
+            }
+            buf.push('\n');
+            buf.push_str(&section);
+        }
+    }
+}
+
+// Returns true if command was replaced
+fn replace_command(buf: &mut String, command_name: &str, new_command_text: &str) -> bool {
+    let command_range = buf.find(command_name).and_then(|start_pos| {
+        cmake::generator::command_len(&buf[start_pos..]).map(|len| (start_pos, start_pos + len))
+    });
+
+    if let Some((start, end)) = command_range {
+        buf.replace_range(start..end, new_command_text.trim());
+        true
+    } else {
+        false
+    }
+}
+
+#[derive(Debug)]
+struct InvalidPath;
+
+impl fmt::Display for InvalidPath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid path")
+    }
+}
+
+impl Error for InvalidPath {
+    fn description(&self) -> &str {
+        "invalid path"
+    }
+}
+
+#[derive(Debug)]
+struct LibraryProject;
+
+impl fmt::Display for LibraryProject {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "the project is a library")
+    }
+}
+
+impl Error for LibraryProject {
+    fn description(&self) -> &str {
+        "the project is a library"
+    }
+}
+
+#[derive(Debug)]
+struct MissingMaintainer;
+
+impl fmt::Display for MissingMaintainer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "cbake.toml has no package maintainer")
+    }
+}
+
+impl Error for MissingMaintainer {
+    fn description(&self) -> &str {
+        "cbake.toml has no package maintainer"
+    }
+}
+
+#[derive(Debug)]
+struct ForeignTarget;
+
+impl fmt::Display for ForeignTarget {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Target does not match the host")
+    }
+}
+
+impl Error for ForeignTarget {
+    fn description(&self) -> &str {
+        "Target does not match the host"
+    }
+}
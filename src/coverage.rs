@@ -0,0 +1,381 @@
+/// This module collects line coverage with gcov and writes coverage reports
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+// Flags for both compiling and linking an instrumented build. Understood by gcc and clang
+pub const COVERAGE_FLAGS: &str = "--coverage";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Line {
+    pub count: Option<u64>, // None if the line has no executable code
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileCoverage {
+    pub path: PathBuf,
+    pub lines: BTreeMap<u32, Line>,
+}
+
+impl FileCoverage {
+    pub fn executable_lines(&self) -> usize {
+        self.lines.values().filter(|line| line.count.is_some()).count()
+    }
+
+    pub fn covered_lines(&self) -> usize {
+        self.lines.values().filter(|line| line.count.unwrap_or(0) > 0).count()
+    }
+
+    pub fn percent(&self) -> f64 {
+        percent(self.covered_lines(), self.executable_lines())
+    }
+
+    // Headers included into several translation units have a gcov file for each of them
+    fn merge(&mut self, other: FileCoverage) {
+        for (number, line) in other.lines {
+            let entry = self.lines.entry(number).or_insert(Line {
+                count: None,
+                text: line.text,
+            });
+
+            entry.count = match (entry.count, line.count) {
+                (Some(a), Some(b)) => Some(a + b),
+                (a, b) => a.or(b),
+            };
+        }
+    }
+}
+
+pub fn percent(covered: usize, total: usize) -> f64 {
+    if total == 0 {
+        100.0
+    } else {
+        covered as f64 * 100.0 / total as f64
+    }
+}
+
+// gcov for gcc builds, llvm-cov in gcov mode for clang builds
+pub fn gcov_tool(build_dir: &Path) -> Vec<String> {
+    let cache = fs::read_to_string(build_dir.join("CMakeCache.txt")).unwrap_or_default();
+    let is_clang = cache
+        .lines()
+        .any(|line| line.starts_with("CMAKE_CXX_COMPILER_ID:") && line.contains("Clang"));
+
+    if is_clang {
+        vec![String::from("llvm-cov"), String::from("gcov")]
+    } else {
+        vec![String::from("gcov")]
+    }
+}
+
+// Removes counters of the previous run so that coverage reflects only the upcoming one
+pub fn reset_counters(build_dir: &Path) -> io::Result<()> {
+    for gcda in find_files(build_dir, "gcda")? {
+        fs::remove_file(gcda)?;
+    }
+
+    Ok(())
+}
+
+// Runs gcov for every counter file in the build directory and keeps only the given sources
+pub fn collect(gcov: &[String], build_dir: &Path, sources: &[PathBuf]) -> io::Result<Vec<FileCoverage>> {
+    let scratch_dir = build_dir.join("gcov");
+    let mut files: BTreeMap<PathBuf, FileCoverage> = BTreeMap::new();
+
+    for gcda in find_files(build_dir, "gcda")? {
+        if scratch_dir.exists() {
+            fs::remove_dir_all(&scratch_dir)?;
+        }
+        fs::create_dir_all(&scratch_dir)?;
+
+        let object_dir = gcda.parent().unwrap_or(build_dir);
+
+        let output = Command::new(&gcov[0])
+            .args(&gcov[1..])
+            .arg("-p")
+            .arg("-o")
+            .arg(object_dir)
+            .arg(&gcda)
+            .current_dir(&scratch_dir)
+            .stdin(Stdio::null())
+            .output()?;
+
+        // A report missing some of the files would look like poor coverage
+        if !output.status.success() {
+            return Err(io::Error::other(format!(
+                "{} failed on {} ({}): {}",
+                gcov.join(" "),
+                gcda.display(),
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+
+        for gcov_file in find_files(&scratch_dir, "gcov")? {
+            let coverage = match parse_gcov(&fs::read_to_string(gcov_file)?) {
+                Some(coverage) => coverage,
+                None => continue,
+            };
+
+            let path = coverage.path.canonicalize().unwrap_or_else(|_| coverage.path.clone());
+
+            if !sources.contains(&path) {
+                continue;
+            }
+
+            match files.get_mut(&path) {
+                Some(file) => file.merge(coverage),
+                None => {
+                    files.insert(path.clone(), FileCoverage { path, ..coverage });
+                }
+            }
+        }
+    }
+
+    if scratch_dir.exists() {
+        fs::remove_dir_all(&scratch_dir)?;
+    }
+
+    Ok(files.into_values().collect())
+}
+
+// Parses a file in gcov text format:
+//         -:    0:Source:/project/main.cpp
+//         1:    4:int main() {
+//     #####:    5:  unused();
+pub fn parse_gcov(contents: &str) -> Option<FileCoverage> {
+    let mut path = None;
+    let mut lines = BTreeMap::new();
+
+    for line in contents.lines() {
+        let mut fields = line.splitn(3, ':');
+
+        let count = fields.next()?.trim();
+        let number: u32 = match fields.next().and_then(|number| number.trim().parse().ok()) {
+            Some(number) => number,
+            None => continue, // Branch and call summaries
+        };
+        let text = fields.next().unwrap_or("");
+
+        if number == 0 {
+            if let Some(source) = text.strip_prefix("Source:") {
+                path = Some(PathBuf::from(source));
+            }
+            continue;
+        }
+
+        let count = match count.trim_end_matches('*') {
+            "-" => None,
+            "#####" | "=====" => Some(0),
+            count => Some(count.parse().ok()?),
+        };
+
+        lines.insert(
+            number,
+            Line {
+                count,
+                text: text.to_string(),
+            },
+        );
+    }
+
+    Some(FileCoverage { path: path?, lines })
+}
+
+pub fn lcov_info(files: &[FileCoverage]) -> String {
+    let mut info = String::new();
+
+    for file in files {
+        info += "TN:\n";
+        info += &format!("SF:{}\n", file.path.display());
+
+        for (number, line) in &file.lines {
+            if let Some(count) = line.count {
+                info += &format!("DA:{},{}\n", number, count);
+            }
+        }
+
+        info += &format!("LF:{}\n", file.executable_lines());
+        info += &format!("LH:{}\n", file.covered_lines());
+        info += "end_of_record\n";
+    }
+
+    info
+}
+
+pub fn html_report(title: &str, root: &Path, files: &[FileCoverage]) -> String {
+    let covered: usize = files.iter().map(FileCoverage::covered_lines).sum();
+    let total: usize = files.iter().map(FileCoverage::executable_lines).sum();
+
+    let mut html = String::new();
+
+    html += "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n";
+    html += &format!("<title>Coverage: {}</title>\n", escape_html(title));
+    html += "<style>\n";
+    html += "body { font-family: sans-serif; }\n";
+    html += "table { border-collapse: collapse; }\n";
+    html += "td, th { padding: 2px 8px; text-align: left; }\n";
+    html += "pre { margin: 0; }\n";
+    html += ".hit { background: #dfd; }\n";
+    html += ".miss { background: #fdd; }\n";
+    html += "</style>\n</head>\n<body>\n";
+    html += &format!("<h1>Coverage: {}</h1>\n", escape_html(title));
+
+    html += "<table>\n<tr><th>File</th><th>Lines</th><th>Coverage</th></tr>\n";
+    for (index, file) in files.iter().enumerate() {
+        html += &format!(
+            "<tr><td><a href=\"#file{}\">{}</a></td><td>{}/{}</td><td>{:.1}%</td></tr>\n",
+            index,
+            escape_html(&display_path(root, &file.path)),
+            file.covered_lines(),
+            file.executable_lines(),
+            file.percent()
+        );
+    }
+    html += &format!(
+        "<tr><th>Total</th><th>{}/{}</th><th>{:.1}%</th></tr>\n</table>\n",
+        covered,
+        total,
+        percent(covered, total)
+    );
+
+    for (index, file) in files.iter().enumerate() {
+        html += &format!(
+            "<h2 id=\"file{}\">{}</h2>\n<table>\n",
+            index,
+            escape_html(&display_path(root, &file.path))
+        );
+
+        for (number, line) in &file.lines {
+            let (class, count) = match line.count {
+                Some(0) => ("miss", String::from("0")),
+                Some(count) => ("hit", count.to_string()),
+                None => ("", String::new()),
+            };
+
+            html += &format!(
+                "<tr class=\"{}\"><td>{}</td><td>{}</td><td><pre>{}</pre></td></tr>\n",
+                class,
+                number,
+                count,
+                escape_html(&line.text)
+            );
+        }
+
+        html += "</table>\n";
+    }
+
+    html += "</body>\n</html>\n";
+    html
+}
+
+// Path relative to the project root if possible
+pub fn display_path(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root).unwrap_or(path).display().to_string()
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn find_files(dir: &Path, extension: &str) -> io::Result<Vec<PathBuf>> {
+    let mut found = Vec::new();
+
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+
+        if path.is_dir() {
+            found.append(&mut find_files(&path, extension)?);
+        } else if path.extension().is_some_and(|ext| ext == extension) {
+            found.push(path);
+        }
+    }
+
+    found.sort();
+    Ok(found)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAIN_GCOV: &str = "        -:    0:Source:/project/main.cpp
+        -:    0:Graph:/project/build/main.cpp.gcno
+        -:    0:Runs:1
+        -:    1:#include <iostream>
+        1:    2:int main(int argc, char** argv) {
+        1:    3:  if (argc > 5) {
+    #####:    4:    std::cout << \"many\" << std::endl;
+        -:    5:  }
+       12*:    6:  return 0;
+        -:    7:}
+branch  0 taken 1
+";
+
+    #[cfg(unix)]
+    #[test]
+    fn failed_gcov() {
+        let scratch = crate::scratch::ScratchDir::new("gcov");
+        let build_dir = scratch.path();
+        fs::write(build_dir.join("main.cpp.gcda"), "").unwrap();
+
+        let err = collect(&[String::from("false")], build_dir, &[]).unwrap_err();
+        assert!(err.to_string().starts_with("false failed on"), "{}", err);
+
+        // Without counter files gcov isn't run at all
+        fs::remove_file(build_dir.join("main.cpp.gcda")).unwrap();
+        assert!(collect(&[String::from("false")], build_dir, &[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn parse() {
+        let file = parse_gcov(MAIN_GCOV).unwrap();
+
+        assert_eq!(file.path, PathBuf::from("/project/main.cpp"));
+        assert_eq!(file.lines.len(), 7);
+        assert_eq!(file.lines[&1].count, None);
+        assert_eq!(file.lines[&4].count, Some(0));
+        assert_eq!(file.lines[&4].text, "    std::cout << \"many\" << std::endl;");
+        assert_eq!(file.lines[&6].count, Some(12));
+
+        assert_eq!(file.executable_lines(), 4);
+        assert_eq!(file.covered_lines(), 3);
+        assert_eq!(file.percent(), 75.0);
+
+        assert_eq!(parse_gcov("        1:    1:int x;\n"), None);
+    }
+
+    #[test]
+    fn merge() {
+        let mut file = parse_gcov(MAIN_GCOV).unwrap();
+        let mut other = file.clone();
+        other.lines.get_mut(&4).unwrap().count = Some(2);
+
+        file.merge(other);
+
+        assert_eq!(file.lines[&2].count, Some(2));
+        assert_eq!(file.lines[&4].count, Some(2));
+        assert_eq!(file.lines[&5].count, None);
+    }
+
+    #[test]
+    fn reports() {
+        let files = vec![parse_gcov(MAIN_GCOV).unwrap()];
+
+        assert_eq!(
+            lcov_info(&files),
+            "TN:\nSF:/project/main.cpp\nDA:2,1\nDA:3,1\nDA:4,0\nDA:6,12\nLF:4\nLH:3\nend_of_record\n"
+        );
+
+        let html = html_report("demo", Path::new("/project"), &files);
+        assert!(html.contains("<td><a href=\"#file0\">main.cpp</a></td><td>3/4</td><td>75.0%</td>"));
+        assert!(html.contains("#include &lt;iostream&gt;"));
+    }
+}
@@ -0,0 +1,324 @@
+/// This module parses compiler diagnostics from the build output and renders them
+use ansi_term::Colour;
+use regex::Regex;
+use serde::Serialize;
+
+use std::{
+    fmt, fs,
+    path::{Component, Path, PathBuf},
+    sync::OnceLock,
+};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub file: String,
+    pub line: u32,
+    pub column: Option<u32>,
+    pub message: String,
+}
+
+impl Severity {
+    fn colour(self) -> Colour {
+        match self {
+            Severity::Error => Colour::Red,
+            Severity::Warning => Colour::Yellow,
+            Severity::Note => Colour::Cyan,
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Note => "note",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+impl Diagnostic {
+    // Renders the diagnostic in the cargo style with a snippet of the source file
+    pub fn render(&self, project_root: &Path) -> String {
+        let path = Path::new(&self.file);
+        let display_path = path.strip_prefix(project_root).unwrap_or(path).display().to_string();
+
+        let source_line = fs::read_to_string(path).ok().and_then(|source| {
+            let index = self.line.checked_sub(1)? as usize;
+            source.lines().nth(index).map(String::from)
+        });
+
+        self.render_with_source(&display_path, source_line.as_deref())
+    }
+
+    pub fn render_with_source(&self, display_path: &str, source_line: Option<&str>) -> String {
+        let severity_colour = self.severity.colour().bold();
+        let gutter_colour = Colour::Blue.bold();
+
+        let line_number = self.line.to_string();
+        let gutter = " ".repeat(line_number.len());
+
+        let mut location = format!("{}:{}", display_path, self.line);
+        if let Some(column) = self.column {
+            location = format!("{}:{}", location, column);
+        }
+
+        let mut rendered = format!(
+            "{}{}\n{}{} {}\n",
+            severity_colour.paint(self.severity.to_string()),
+            Colour::White.bold().paint(format!(": {}", self.message)),
+            gutter,
+            gutter_colour.paint("-->"),
+            location
+        );
+
+        if let Some(source_line) = source_line {
+            rendered += &format!("{} {}\n", gutter, gutter_colour.paint("|"));
+            rendered += &format!("{} {} {}\n", gutter_colour.paint(&line_number), gutter_colour.paint("|"), source_line);
+
+            if let Some(column) = self.column {
+                // Keep tabs so the caret lines up with the source
+                let padding: String = source_line
+                    .chars()
+                    .take(column.saturating_sub(1) as usize)
+                    .map(|c| if c == '\t' { '\t' } else { ' ' })
+                    .collect();
+
+                rendered += &format!(
+                    "{} {} {}{}\n",
+                    gutter,
+                    gutter_colour.paint("|"),
+                    padding,
+                    severity_colour.paint("^")
+                );
+            }
+        }
+
+        rendered
+    }
+}
+
+// Error and warning counts of a build
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Serialize)]
+pub struct Summary {
+    pub errors: usize,
+    pub warnings: usize,
+}
+
+impl Summary {
+    pub fn new(diagnostics: &[Diagnostic]) -> Self {
+        let count = |severity| diagnostics.iter().filter(|d| d.severity == severity).count();
+
+        Summary {
+            errors: count(Severity::Error),
+            warnings: count(Severity::Warning),
+        }
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let plural = |n: usize| if n == 1 { "" } else { "s" };
+
+        write!(
+            f,
+            "{} error{}, {} warning{}",
+            self.errors,
+            plural(self.errors),
+            self.warnings,
+            plural(self.warnings)
+        )
+    }
+}
+
+// Finds GCC/Clang (file:line:col: error: ...) and MSVC (file(line,col): error C1234: ...) diagnostics.
+// The same diagnostic may be reported for every translation unit including a header, duplicates are dropped.
+// Compilers run in the build directory, so relative paths like ../main.cpp of ninja builds are resolved against it
+pub fn parse(output: &[String], build_dir: &Path) -> Vec<Diagnostic> {
+    static GCC_PATTERN: OnceLock<Regex> = OnceLock::new();
+    static MSVC_PATTERN: OnceLock<Regex> = OnceLock::new();
+
+    let gcc_pattern = GCC_PATTERN.get_or_init(|| {
+        Regex::new(
+            r"^(?P<file>(?:[A-Za-z]:)?[^:]+):(?P<line>\d+):(?:(?P<column>\d+):)?\s+(?P<severity>fatal error|error|warning|note):\s+(?P<message>.*)$",
+        )
+        .unwrap()
+    });
+    let msvc_pattern = MSVC_PATTERN.get_or_init(|| {
+        Regex::new(
+            r"^\s*(?P<file>[^(]+)\((?P<line>\d+)(?:,(?P<column>\d+))?\)\s*:\s+(?P<severity>fatal error|error|warning|note)(?:\s+[A-Z]+\d+)?\s*:\s+(?P<message>.*)$",
+        )
+        .unwrap()
+    });
+
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
+
+    for line in output {
+        let captures = match gcc_pattern.captures(line).or_else(|| msvc_pattern.captures(line)) {
+            Some(captures) => captures,
+            None => continue,
+        };
+
+        let severity = match &captures["severity"] {
+            "warning" => Severity::Warning,
+            "note" => Severity::Note,
+            _ => Severity::Error,
+        };
+
+        let diagnostic = Diagnostic {
+            severity,
+            file: resolve_path(captures["file"].trim(), build_dir),
+            line: captures["line"].parse().unwrap_or(0),
+            column: captures.name("column").and_then(|column| column.as_str().parse().ok()),
+            message: captures["message"].trim().to_string(),
+        };
+
+        if diagnostic.line > 0 && !diagnostics.contains(&diagnostic) {
+            diagnostics.push(diagnostic);
+        }
+    }
+
+    diagnostics
+}
+
+fn resolve_path(file: &str, build_dir: &Path) -> String {
+    // Windows paths aren't absolute for Path on other systems
+    let is_windows_absolute = file.len() > 2 && file.as_bytes()[1] == b':' && file.as_bytes()[0].is_ascii_alphabetic();
+
+    if Path::new(file).is_absolute() || is_windows_absolute {
+        return file.to_string();
+    }
+
+    // `..` is removed without touching the file system, the file may be gone by now
+    let mut path = PathBuf::new();
+    for component in build_dir.join(file).components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir if matches!(path.components().next_back(), Some(Component::Normal(_))) => {
+                path.pop();
+            }
+            component => path.push(component),
+        }
+    }
+
+    path.display().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scratch::ScratchDir;
+
+    fn lines(output: &str) -> Vec<String> {
+        output.lines().map(String::from).collect()
+    }
+
+    #[test]
+    fn parse_gcc_and_clang() {
+        let output = lines(
+            "[ 50%] Building CXX object CMakeFiles/demo.dir/main.cpp.o
+/home/user/demo/main.cpp: In function 'int main(int, char**)':
+/home/user/demo/main.cpp:4:7: warning: unused variable 'x' [-Wunused-variable]
+    4 |   int x = 5;
+      |       ^
+/home/user/demo/main.cpp:5:3: error: 'foo' was not declared in this scope
+/home/user/demo/util.h:2: error: expected ';' after return statement
+/home/user/demo/main.cpp:4:7: warning: unused variable 'x' [-Wunused-variable]
+make[2]: *** [CMakeFiles/demo.dir/build.make:76: CMakeFiles/demo.dir/main.cpp.o] Error 1",
+        );
+
+        let diagnostics = parse(&output, Path::new("/home/user/demo/build"));
+        assert_eq!(diagnostics.len(), 3);
+
+        assert_eq!(
+            diagnostics[0],
+            Diagnostic {
+                severity: Severity::Warning,
+                file: String::from("/home/user/demo/main.cpp"),
+                line: 4,
+                column: Some(7),
+                message: String::from("unused variable 'x' [-Wunused-variable]"),
+            }
+        );
+        assert_eq!(diagnostics[1].severity, Severity::Error);
+        assert_eq!(diagnostics[2].file, "/home/user/demo/util.h");
+        assert_eq!(diagnostics[2].column, None);
+
+        assert_eq!(Summary::new(&diagnostics), Summary { errors: 2, warnings: 1 });
+        assert_eq!(Summary::new(&diagnostics).to_string(), "2 errors, 1 warning");
+    }
+
+    #[test]
+    fn parse_msvc() {
+        let output = lines(
+            r"  C:\demo\main.cpp(4,7): warning C4101: 'x': unreferenced local variable
+C:\demo\main.cpp(5): fatal error C1083: Cannot open include file: 'foo.h': No such file or directory",
+        );
+
+        let diagnostics = parse(&output, Path::new("/demo/build"));
+        assert_eq!(diagnostics.len(), 2);
+
+        assert_eq!(diagnostics[0].file, r"C:\demo\main.cpp");
+        assert_eq!(diagnostics[0].line, 4);
+        assert_eq!(diagnostics[0].column, Some(7));
+        assert_eq!(diagnostics[0].message, "'x': unreferenced local variable");
+        assert_eq!(diagnostics[1].severity, Severity::Error);
+        assert_eq!(diagnostics[1].column, None);
+    }
+
+    #[test]
+    fn relative_paths() {
+        let output = lines("../main.cpp:3:1: error: expected ';'\n./gen/config.h:1:1: warning: empty file");
+
+        let diagnostics = parse(&output, Path::new("/demo/build/Debug"));
+        assert_eq!(diagnostics[0].file, "/demo/build/main.cpp");
+        assert_eq!(diagnostics[1].file, "/demo/build/Debug/gen/config.h");
+    }
+
+    #[test]
+    fn render() {
+        let diagnostic = Diagnostic {
+            severity: Severity::Error,
+            file: String::from("/demo/main.cpp"),
+            line: 12,
+            column: Some(5),
+            message: String::from("expected ';'"),
+        };
+
+        let rendered = diagnostic.render_with_source("main.cpp", Some("\tfoo()"));
+        let rendered = rendered.lines().collect::<Vec<_>>();
+
+        assert_eq!(rendered.len(), 5);
+        assert!(rendered[0].contains("error") && rendered[0].contains("expected ';'"));
+        assert!(rendered[1].starts_with("  ") && rendered[1].ends_with(" main.cpp:12:5"));
+        assert!(rendered[3].ends_with(" \tfoo()"));
+        assert!(rendered[4].contains(" \t   "));
+    }
+
+    #[test]
+    fn render_line_zero() {
+        let dir = ScratchDir::new("diagnostic");
+        let file = dir.join("main.cpp");
+        fs::write(&file, "int main() {}\n").unwrap();
+
+        let diagnostic = Diagnostic {
+            severity: Severity::Error,
+            file: file.display().to_string(),
+            line: 0,
+            column: None,
+            message: String::from("expected ';'"),
+        };
+        assert_eq!(diagnostic.render(dir.path()).lines().count(), 2);
+    }
+}
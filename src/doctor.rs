@@ -0,0 +1,523 @@
+/// This module checks the environment and the current project for problems
+use crate::cmake::{self, builder, generator, Version};
+use crate::commands;
+use crate::manifest::{self, Manifest, ProjectKind};
+
+use serde::Serialize;
+
+use std::{
+    collections::HashMap,
+    env,
+    ffi::OsStr,
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Ok,
+    Warning,
+    Error,
+}
+
+// Result of a single check with a suggestion how to fix it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Check {
+    pub name: String,
+    pub status: CheckStatus,
+    pub details: String,
+    pub help: Option<String>,
+}
+
+impl Check {
+    fn ok<S: Into<String>>(name: &str, details: S) -> Self {
+        Check {
+            name: name.to_string(),
+            status: CheckStatus::Ok,
+            details: details.into(),
+            help: None,
+        }
+    }
+
+    fn warning<S: Into<String>>(name: &str, details: S, help: S) -> Self {
+        Check {
+            name: name.to_string(),
+            status: CheckStatus::Warning,
+            details: details.into(),
+            help: Some(help.into()),
+        }
+    }
+
+    fn error<S: Into<String>>(name: &str, details: S, help: S) -> Self {
+        Check {
+            name: name.to_string(),
+            status: CheckStatus::Error,
+            details: details.into(),
+            help: Some(help.into()),
+        }
+    }
+}
+
+// Runs `program --version` and returns the first line it prints
+pub fn tool_version<P: AsRef<OsStr>>(program: P) -> Option<String> {
+    let output = Command::new(program).arg("--version").output().ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    // Some tools print the version to stderr
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    stdout
+        .lines()
+        .chain(stderr.lines())
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .map(String::from)
+}
+
+// Checks tools cbake runs directly or through cmake
+pub fn tool_checks() -> Vec<Check> {
+    let mut checks = Vec::new();
+
+    checks.push(match cmake::exec::version() {
+        Ok(version) if version < Version::new(2, 8) => Check::error(
+            "cmake",
+            format!("{} is too old", version),
+            String::from("cbake generates CMakeLists.txt for cmake 2.8 or newer, install a newer cmake"),
+        ),
+        Ok(version) => Check::ok("cmake", format!("{} ({})", version, cmake::exec::cmake_program().display())),
+        Err(e) => Check::error(
+            "cmake",
+            format!("not found ({})", e),
+            String::from("Install cmake and make sure it is present in PATH environment variable or set CBAKE_CMAKE"),
+        ),
+    });
+
+    let ctest = cmake::exec::ctest_program();
+
+    checks.push(match tool_version(&ctest) {
+        Some(version) => Check::ok("ctest", format!("{} ({})", version, ctest.display())),
+        None => Check::error(
+            "ctest",
+            "not found",
+            "ctest is shipped with cmake, `cbake test` needs it. Reinstall cmake",
+        ),
+    });
+
+    let cpack = cmake::exec::cpack_program();
+
+    checks.push(match tool_version(&cpack) {
+        Some(version) => Check::ok("cpack", format!("{} ({})", version, cpack.display())),
+        None => Check::warning(
+            "cpack",
+            String::from("not found"),
+            String::from("cpack is shipped with cmake, `cbake package` needs it. Reinstall cmake"),
+        ),
+    });
+
+    checks.push(compiler_check("C compiler", "CC", &["cc", "gcc", "clang"]));
+    checks.push(compiler_check("C++ compiler", "CXX", &["c++", "g++", "clang++"]));
+
+    let make = tool_version("make");
+    let ninja = tool_version("ninja");
+
+    checks.push(match (&make, &ninja) {
+        (None, None) => Check::error(
+            "build tool",
+            "neither make nor ninja found",
+            "Install make or ninja, cmake generates build files for one of them",
+        ),
+        (Some(version), _) => Check::ok("build tool", version.clone()),
+        (None, Some(version)) => Check::warning(
+            "build tool",
+            format!("make not found, {}", version),
+            String::from("Set CMAKE_GENERATOR=Ninja so that cmake uses ninja"),
+        ),
+    });
+
+    let optional_tools = [
+        ("ninja", "Faster builds: set CMAKE_GENERATOR=Ninja after installing ninja"),
+        ("clang-format", "Install clang-format to format the project sources"),
+        ("gcov", "`cbake test --coverage` needs gcov for gcc builds, it is shipped with gcc"),
+        ("llvm-cov", "`cbake test --coverage` needs llvm-cov for clang builds, it is shipped with llvm"),
+    ];
+
+    for (tool, help) in optional_tools.iter() {
+        checks.push(match tool_version(tool) {
+            Some(version) => Check::ok(tool, version),
+            None => Check::warning(tool, "not found", help),
+        });
+    }
+
+    // Runners configured for foreign targets
+    let mut runners: Vec<(String, String)> = env::vars()
+        .filter(|(var, _)| var.starts_with("CBAKE_TARGET_") && var.ends_with("_RUNNER"))
+        .collect();
+    runners.sort();
+
+    for (var, runner) in runners {
+        let program = runner.split_whitespace().next().unwrap_or_default();
+
+        checks.push(match tool_version(program) {
+            Some(version) => Check::ok(&var, version),
+            None => Check::error(
+                &var,
+                format!("runner `{}` can't be started", program),
+                format!("Install {} or fix {}", program, var),
+            ),
+        });
+    }
+
+    checks
+}
+
+// CMake uses the compiler from the environment variable, then looks for the default ones
+fn compiler_check(name: &str, env_var: &str, defaults: &[&str]) -> Check {
+    if let Ok(compiler) = env::var(env_var) {
+        return match tool_version(&compiler) {
+            Some(version) => Check::ok(name, version),
+            None => Check::error(
+                name,
+                format!("{}={} can't be started", env_var, compiler),
+                format!("Install {} or unset {}", compiler, env_var),
+            ),
+        };
+    }
+
+    defaults
+        .iter()
+        .find_map(tool_version)
+        .map(|version| Check::ok(name, version))
+        .unwrap_or_else(|| {
+            Check::error(
+                name,
+                String::from("not found"),
+                format!("Install gcc or clang, or set {} to the compiler path", env_var),
+            )
+        })
+}
+
+// Checks the manifest, CMakeLists.txt and cmake caches of the project
+pub fn project_checks(project_path: &Path, build_path: &Path, cmake_version: Option<Version>) -> Vec<Check> {
+    let cmake_lists = match fs::read_to_string(project_path.join("CMakeLists.txt")) {
+        Ok(cmake_lists) => cmake_lists,
+        Err(_) => {
+            return vec![Check::warning(
+                "project",
+                "no CMakeLists.txt in the project directory, project checks are skipped",
+                "Run `cbake doctor` inside a project or point to it with -C or --manifest-path",
+            )]
+        }
+    };
+
+    let kind = fs::File::open(project_path.join(manifest::MANIFEST_FILE))
+        .ok()
+        .and_then(|file| Manifest::from_reader(file).ok())
+        .map(|manifest| manifest.package.kind)
+        .unwrap_or_default();
+
+    let mut checks = vec![
+        manifest_check(project_path),
+        cmake_lists_check(&cmake_lists, kind, cmake_version),
+    ];
+
+    for cache_path in find_caches(build_path) {
+        if let Ok(cache) = fs::read_to_string(&cache_path) {
+            let build_dir = cache_path.parent().unwrap_or(build_path);
+            let name = format!("cache {}", build_dir.strip_prefix(project_path).unwrap_or(build_dir).display());
+
+            checks.push(cache_check(&name, &parse_cache(&cache), project_path, build_dir));
+        }
+    }
+
+    checks
+}
+
+fn manifest_check(project_path: &Path) -> Check {
+    let name = manifest::MANIFEST_FILE;
+
+    let manifest = match fs::File::open(project_path.join(name)) {
+        Ok(file) => Manifest::from_reader(file),
+        Err(_) => {
+            return Check::warning(
+                name,
+                "not found, default profiles are used",
+                "Create cbake.toml with a [package] section to pin the project name",
+            )
+        }
+    };
+
+    let broken_profile = manifest.as_ref().ok().and_then(|manifest| {
+        manifest
+            .profile_names()
+            .iter()
+            .find_map(|profile| manifest.profile(profile).err())
+    });
+
+    match (manifest, broken_profile) {
+        (Err(e), _) | (Ok(_), Some(e)) => Check::error(
+            name,
+            e.to_string(),
+            String::from("Fix the manifest. Known sections are [package], [build], [profile.<name>] and [target.<triple>]"),
+        ),
+        (Ok(manifest), None) => Check::ok(name, format!("profiles: {}", manifest.profile_names().join(", "))),
+    }
+}
+
+// cbake rewrites the add_executable (add_library for libraries) command on every build,
+// the rest of the file belongs to the user
+fn cmake_lists_check(cmake_lists: &str, kind: ProjectKind, cmake_version: Option<Version>) -> Check {
+    let name = "CMakeLists.txt";
+    let command = match kind {
+        ProjectKind::Executable => "add_executable",
+        ProjectKind::Library => "add_library",
+    };
+
+    let sections = [
+        ("settings", builder::SETTINGS_SECTION_BEGIN, builder::SETTINGS_SECTION_END),
+        ("install", commands::INSTALL_SECTION_BEGIN, commands::INSTALL_SECTION_END),
+    ];
+
+    // A lone marker makes cbake append a second section instead of replacing the existing one
+    for (section, begin, end) in sections.iter() {
+        if let Some(details) = section_markers_error(cmake_lists, section, begin, end) {
+            return Check::error(
+                name,
+                details,
+                format!("Remove the {} section with its comment lines, cbake writes it again", section),
+            );
+        }
+    }
+
+    let commands = cmake_lists.matches(command).count();
+    let unclosed = cmake_lists
+        .find(command)
+        .is_some_and(|start| generator::command_len(&cmake_lists[start..]).is_none());
+
+    if commands > 1 || unclosed {
+        let details = if unclosed {
+            format!("{} command is not closed", command)
+        } else {
+            format!("several {} commands, cbake rewrites only the first one", command)
+        };
+
+        return Check::error(
+            name,
+            details,
+            format!("Keep a single {} command, cbake fills in the project sources", command),
+        );
+    }
+
+    let min_version = builder::min_version(cmake_lists);
+
+    match (min_version, cmake_version) {
+        (Some(required), Some(installed)) if installed < required => Check::error(
+            name,
+            format!("requires cmake {}, installed {}", required, installed),
+            String::from("Install a newer cmake"),
+        ),
+        (None, _) => Check::warning(
+            name,
+            "cmake_minimum_required is missing",
+            "Add cmake_minimum_required(VERSION <version>) at the top of the file",
+        ),
+        _ if commands == 0 => Check::ok(name, format!("{} will be added on the next build", command)),
+        _ => Check::ok(name, format!("{} is managed by cbake", command)),
+    }
+}
+
+fn section_markers_error(cmake_lists: &str, section: &str, begin: &str, end: &str) -> Option<String> {
+    let begins = cmake_lists.matches(begin).count();
+    let ends = cmake_lists.matches(end).count();
+
+    if begins > 1 || ends > 1 {
+        return Some(format!("several {} sections", section));
+    }
+
+    match (cmake_lists.find(begin), cmake_lists.find(end)) {
+        (Some(_), None) => Some(format!("{} section is not closed", section)),
+        (None, Some(_)) => Some(format!("end of the {} section without its beginning", section)),
+        (Some(begin), Some(end)) if end < begin => Some(format!("{} section ends before it begins", section)),
+        _ => None,
+    }
+}
+
+// Build directories contain nested ones for targets, sanitizers and coverage
+fn find_caches(build_path: &Path) -> Vec<PathBuf> {
+    let mut caches = Vec::new();
+    let mut dirs = vec![build_path.to_path_buf()];
+
+    while let Some(dir) = dirs.pop() {
+        let cache = dir.join("CMakeCache.txt");
+        if cache.is_file() {
+            caches.push(cache);
+        }
+
+        if let Ok(entries) = fs::read_dir(&dir) {
+            for entry in entries.filter_map(Result::ok) {
+                let path = entry.path();
+                if path.is_dir() && entry.file_name() != "CMakeFiles" {
+                    dirs.push(path);
+                }
+            }
+        }
+    }
+
+    caches.sort();
+    caches
+}
+
+// CMakeCache.txt entries look like NAME:TYPE=VALUE
+pub fn parse_cache(cache: &str) -> HashMap<String, String> {
+    cache
+        .lines()
+        .filter(|line| !line.starts_with('#') && !line.starts_with("//"))
+        .filter_map(|line| {
+            let (key, value) = line.split_at(line.find('=')?);
+            let name = key.split(':').next()?;
+
+            Some((name.to_string(), value[1..].to_string()))
+        })
+        .collect()
+}
+
+fn cache_check(name: &str, cache: &HashMap<String, String>, project_path: &Path, build_dir: &Path) -> Check {
+    let remove_help = format!("Remove {} and build again", build_dir.display());
+
+    if let Some(home) = cache.get("CMAKE_HOME_DIRECTORY") {
+        if !same_path(Path::new(home), project_path) {
+            return Check::error(
+                name,
+                format!("stale cache, it was created for {}", home),
+                remove_help,
+            );
+        }
+    }
+
+    if let Some(command) = cache.get("CMAKE_COMMAND") {
+        if !Path::new(command).exists() {
+            return Check::error(name, format!("stale cache, {} no longer exists", command), remove_help);
+        }
+    }
+
+    for compiler in ["CMAKE_C_COMPILER", "CMAKE_CXX_COMPILER"].iter() {
+        if let Some(path) = cache.get(*compiler) {
+            if Path::new(path).is_absolute() && !Path::new(path).exists() {
+                return Check::error(name, format!("stale cache, {} no longer exists", path), remove_help);
+            }
+        }
+    }
+
+    let generator = cache.get("CMAKE_GENERATOR").cloned().unwrap_or_default();
+
+    if let Ok(requested) = env::var("CMAKE_GENERATOR") {
+        if !requested.is_empty() && !generator.is_empty() && requested != generator {
+            return Check::error(
+                name,
+                format!("generator mismatch, cache uses {} but CMAKE_GENERATOR is {}", generator, requested),
+                remove_help,
+            );
+        }
+    }
+
+    let generator_tool = match generator.as_str() {
+        "Ninja" | "Ninja Multi-Config" => Some("ninja"),
+        "Unix Makefiles" => Some("make"),
+        _ => None,
+    };
+
+    match generator_tool {
+        Some(tool) if tool_version(tool).is_none() => Check::error(
+            name,
+            format!("generator {} needs {} which is not found", generator, tool),
+            format!("Install {} or remove {} to use another generator", tool, build_dir.display()),
+        ),
+        _ => Check::ok(name, generator),
+    }
+}
+
+fn same_path(first: &Path, second: &Path) -> bool {
+    match (first.canonicalize(), second.canonicalize()) {
+        (Ok(first), Ok(second)) => first == second,
+        _ => first == second,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cache() {
+        let cache = parse_cache(
+            "# This is the CMakeCache file.
+//Path to a program.
+CMAKE_COMMAND:INTERNAL=/nonexistent/bin/cmake
+CMAKE_GENERATOR:INTERNAL=Unix Makefiles
+CMAKE_HOME_DIRECTORY:INTERNAL=/nonexistent/demo
+CMAKE_CXX_FLAGS:STRING=-O2 -DNAME=value
+",
+        );
+
+        assert_eq!(cache.len(), 4);
+        assert_eq!(cache["CMAKE_GENERATOR"], "Unix Makefiles");
+        assert_eq!(cache["CMAKE_CXX_FLAGS"], "-O2 -DNAME=value");
+
+        let moved = cache_check("cache", &cache, Path::new("/nonexistent/other"), Path::new("build"));
+        assert_eq!(moved.status, CheckStatus::Error);
+        assert!(moved.details.contains("/nonexistent/demo"));
+
+        let reinstalled = cache_check("cache", &cache, Path::new("/nonexistent/demo"), Path::new("build"));
+        assert_eq!(reinstalled.status, CheckStatus::Error);
+        assert!(reinstalled.details.contains("/nonexistent/bin/cmake"));
+    }
+
+    #[test]
+    fn cmake_lists() {
+        let managed = "cmake_minimum_required(VERSION 3.1)\nproject(demo)\nadd_executable(demo src/main.cpp)\n";
+        assert_eq!(cmake_lists_check(managed, ProjectKind::Executable, Some(Version::new(3, 16))).status, CheckStatus::Ok);
+        assert_eq!(cmake_lists_check(managed, ProjectKind::Executable, Some(Version::new(3, 0))).status, CheckStatus::Error);
+
+        let twice = format!("{}add_executable(tool tool.cpp)\n", managed);
+        assert_eq!(cmake_lists_check(&twice, ProjectKind::Executable, None).status, CheckStatus::Error);
+
+        let unclosed = "cmake_minimum_required(VERSION 3.1)\nadd_executable(demo src/main.cpp\n";
+        assert_eq!(cmake_lists_check(unclosed, ProjectKind::Executable, None).status, CheckStatus::Error);
+
+        assert_eq!(cmake_lists_check("project(demo)", ProjectKind::Executable, None).status, CheckStatus::Warning);
+    }
+
+    #[test]
+    fn section_markers() {
+        let managed = "cmake_minimum_required(VERSION 3.1)\nadd_executable(demo src/main.cpp)\n";
+        let settings = format!("{}\nset(X 1)\n{}\n", builder::SETTINGS_SECTION_BEGIN, builder::SETTINGS_SECTION_END);
+        let install = format!("{}\ninstall(TARGETS demo)\n{}\n", commands::INSTALL_SECTION_BEGIN, commands::INSTALL_SECTION_END);
+
+        let check = |cmake_lists: &str| cmake_lists_check(cmake_lists, ProjectKind::Executable, None);
+
+        assert_eq!(check(&format!("{}{}{}", managed, settings, install)).status, CheckStatus::Ok);
+
+        let unclosed = check(&format!("{}{}\n", managed, builder::SETTINGS_SECTION_BEGIN));
+        assert_eq!(unclosed.status, CheckStatus::Error);
+        assert_eq!(unclosed.details, "settings section is not closed");
+
+        let no_begin = check(&format!("{}{}\n", managed, commands::INSTALL_SECTION_END));
+        assert_eq!(no_begin.status, CheckStatus::Error);
+        assert!(no_begin.details.contains("install"));
+
+        let reversed = format!(
+            "{}{}\n{}\n",
+            managed,
+            commands::INSTALL_SECTION_END,
+            commands::INSTALL_SECTION_BEGIN
+        );
+        assert_eq!(check(&reversed).details, "install section ends before it begins");
+
+        assert_eq!(check(&format!("{}{}{}", managed, settings, settings)).details, "several settings sections");
+    }
+}
@@ -1,50 +1,57 @@
 use std::error::Error;
 use std::fmt;
+use std::str::FromStr;
 
 use ansi_term::Colour;
+use serde::ser::{Serialize, SerializeStruct, Serializer};
 
 // Errors to display to the user
-pub fn version_err(err: Box<dyn Error + 'static>) -> FatalError {
+pub fn version_err<E>(err: E) -> FatalError
+where
+    E: Error + 'static,
+{
     FatalError::with_help(
-            err,
-            "Cannot obtain a cmake version",
-            "Make sure cmake is installed and present in PATH environment variable. Run `cmake --version` to verify cmake installation",
-        )
+        ErrorCode::CMakeVersion,
+        Box::new(err),
+        "Cannot obtain a cmake version",
+        "Make sure cmake is installed and present in PATH environment variable or set CBAKE_CMAKE. \
+         Run `cmake --version` to verify cmake installation",
+    )
 }
 
 pub fn init_err<E>(err: E) -> FatalError
 where
     E: Error + 'static,
 {
-    FatalError::new(Box::new(err), "Cannot initialize cmake build directory")
+    FatalError::new(ErrorCode::CMakeInit, Box::new(err), "Cannot initialize cmake build directory")
 }
 
 pub fn layout_gen_err<E>(err: E) -> FatalError
 where
     E: Error + 'static,
 {
-    FatalError::new(Box::new(err), "Cannot generate a project layout")
+    FatalError::new(ErrorCode::LayoutGeneration, Box::new(err), "Cannot generate a project layout")
 }
 
 pub fn collect_sources_err<E>(err: E) -> FatalError
 where
     E: Error + 'static,
 {
-    FatalError::new(Box::new(err), "Cannot collect project sources")
+    FatalError::new(ErrorCode::CollectSources, Box::new(err), "Cannot collect project sources")
 }
 
 pub fn dir_access_err<E>(err: E) -> FatalError
 where
     E: Error + 'static,
 {
-    FatalError::new(Box::new(err), "Cannot access project directory")
+    FatalError::new(ErrorCode::DirectoryAccess, Box::new(err), "Cannot access project directory")
 }
 
 pub fn cmake_write_err<E>(err: E) -> FatalError
 where
     E: Error + 'static,
 {
-    FatalError::new(Box::new(err), "Cannot write CMakeLists.txt")
+    FatalError::new(ErrorCode::CMakeListsWrite, Box::new(err), "Cannot write CMakeLists.txt")
 }
 
 pub fn cmake_read_err<E>(err: E) -> FatalError
@@ -52,36 +59,465 @@ where
     E: Error + 'static,
 {
     FatalError::with_help(
+        ErrorCode::CMakeListsRead,
         Box::new(err),
         "Cannot read CMakeLists.txt",
-        "Make sure you are located within a project directory",
+        "Run cbake inside a project or point to it with -C or --manifest-path",
+    )
+}
+
+pub fn target_err<E>(err: E) -> FatalError
+where
+    E: Error + 'static,
+{
+    FatalError::with_help(
+        ErrorCode::Target,
+        Box::new(err),
+        "Cannot parse a target",
+        "Target must be a triple like aarch64-linux-gnu or arm-none-eabi",
+    )
+}
+
+pub fn sanitizer_err<E>(err: E) -> FatalError
+where
+    E: Error + 'static,
+{
+    FatalError::with_help(
+        ErrorCode::Sanitizer,
+        Box::new(err),
+        "Cannot enable sanitizers",
+        "Available sanitizers are address, undefined, thread, memory and leak. \
+         Thread sanitizer can't be combined with address, memory or leak sanitizers, \
+         memory sanitizer can't be combined with address or leak sanitizers",
+    )
+}
+
+pub fn coverage_err<E>(err: E) -> FatalError
+where
+    E: Error + 'static,
+{
+    FatalError::with_help(
+        ErrorCode::Coverage,
+        Box::new(err),
+        "Cannot collect coverage",
+        "Coverage reports are made with gcov for gcc builds and llvm-cov for clang builds. Make sure it is installed",
+    )
+}
+
+pub fn compile_commands_err<E>(err: E) -> FatalError
+where
+    E: Error + 'static,
+{
+    FatalError::new(ErrorCode::CompileCommands, Box::new(err), "Cannot export compile_commands.json to the project directory")
+}
+
+pub fn toolchain_write_err<E>(err: E) -> FatalError
+where
+    E: Error + 'static,
+{
+    FatalError::new(ErrorCode::Toolchain, Box::new(err), "Cannot write a toolchain file")
+}
+
+pub fn manifest_read_err<E>(err: E) -> FatalError
+where
+    E: Error + 'static,
+{
+    FatalError::with_help(
+        ErrorCode::ManifestRead,
+        Box::new(err),
+        "Cannot read cbake.toml",
+        "Check the manifest syntax. Known sections are [package], [build], [profile.<name>] and [target.<triple>]",
+    )
+}
+
+pub fn manifest_write_err<E>(err: E) -> FatalError
+where
+    E: Error + 'static,
+{
+    FatalError::new(ErrorCode::ManifestWrite, Box::new(err), "Cannot write cbake.toml")
+}
+
+pub fn profile_err<E>(err: E, known_profiles: &[String]) -> FatalError
+where
+    E: Error + 'static,
+{
+    FatalError::with_help(
+        ErrorCode::Profile,
+        Box::new(err),
+        String::from("Cannot select a build profile"),
+        format!("Available profiles: {}", known_profiles.join(", ")),
+    )
+}
+
+pub fn build_command_err<E>(err: E) -> FatalError
+where
+    E: Error + 'static,
+{
+    FatalError::new(ErrorCode::BuildCommand, Box::new(err), "Cannot run build command")
+}
+
+pub fn run_err<E>(err: E) -> FatalError
+where
+    E: Error + 'static,
+{
+    FatalError::new(ErrorCode::RunExecutable, Box::new(err), "Cannot run executable")
+}
+
+pub fn ctest_err<E>(err: E) -> FatalError
+where
+    E: Error + 'static,
+{
+    FatalError::with_help(
+        ErrorCode::TestCommand,
+        Box::new(err),
+        "Cannot run ctest",
+        "ctest is shipped with cmake. Make sure it is present in PATH environment variable",
+    )
+}
+
+pub fn explain_err<E>(err: E) -> FatalError
+where
+    E: Error + 'static,
+{
+    FatalError::with_help(
+        ErrorCode::Explain,
+        Box::new(err),
+        String::from("Cannot explain an error"),
+        format!("Known error codes are {} to {}", ErrorCode::ALL[0], ErrorCode::ALL[ErrorCode::ALL.len() - 1]),
+    )
+}
+
+pub fn add_source_err<E>(err: E) -> FatalError
+where
+    E: Error + 'static,
+{
+    FatalError::with_help(
+        ErrorCode::AddSource,
+        Box::new(err),
+        "Cannot add source files",
+        "Class and namespace names must be C++ identifiers and the files must not exist yet",
     )
 }
 
+pub fn remove_source_err<E>(err: E) -> FatalError
+where
+    E: Error + 'static,
+{
+    FatalError::new(ErrorCode::RemoveSource, Box::new(err), "Cannot remove source files")
+}
+
+pub fn install_err<E>(err: E) -> FatalError
+where
+    E: Error + 'static,
+{
+    FatalError::new(ErrorCode::Install, Box::new(err), "Cannot install the project")
+}
+
+pub fn package_err<E>(err: E) -> FatalError
+where
+    E: Error + 'static,
+{
+    FatalError::with_help(
+        ErrorCode::Package,
+        Box::new(err),
+        "Cannot package the project",
+        "Packages are made with cpack, which is shipped with cmake. \
+         The [package] section of cbake.toml must have a maintainer",
+    )
+}
+
+pub fn project_name_err<E>(err: E) -> FatalError
+where
+    E: Error + 'static,
+{
+    FatalError::with_help(
+        ErrorCode::ProjectName,
+        Box::new(err),
+        "Invalid project name",
+        "Project names may contain letters and digits of any script, `_`, `.`, `+` and `-` and must start with a \
+         letter or `_`. Choose another name with `cbake new --name <name>`",
+    )
+}
+
+pub fn warnings_err<E>(err: E) -> FatalError
+where
+    E: Error + 'static,
+{
+    FatalError::with_help(
+        ErrorCode::Warnings,
+        Box::new(err),
+        "Cannot set the warning level",
+        "Warning levels are none, default, strict and pedantic",
+    )
+}
+
+pub fn not_implemented_err(command: &str) -> FatalError {
+    let err = std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        format!("`cbake {}` is not implemented yet", command),
+    );
+
+    FatalError::with_help(
+        ErrorCode::NotImplemented,
+        Box::new(err),
+        "Command is not available",
+        "Create a new project with `cbake new <path>`",
+    )
+}
+
+// Stable codes of fatal errors. The numbers must never be reused, scripts rely on them
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ErrorCode {
+    CMakeVersion = 1,
+    CMakeInit = 2,
+    LayoutGeneration = 3,
+    CollectSources = 4,
+    DirectoryAccess = 5,
+    CMakeListsWrite = 6,
+    CMakeListsRead = 7,
+    ManifestRead = 8,
+    ManifestWrite = 9,
+    Profile = 10,
+    Target = 11,
+    Sanitizer = 12,
+    Toolchain = 13,
+    CompileCommands = 14,
+    Coverage = 15,
+    BuildCommand = 16,
+    RunExecutable = 17,
+    TestCommand = 18,
+    ProjectName = 19,
+    ForeignTarget = 20,
+    Explain = 21,
+    AddSource = 22,
+    RemoveSource = 23,
+    Install = 24,
+    Package = 25,
+    Warnings = 26,
+    NotImplemented = 27,
+}
+
+// Exit status of a command which executed without success, e.g. a failed build or a failed test
+pub const FAILURE_EXIT_CODE: i32 = 1;
+
+// Exit statuses of fatal errors are 100 + error code, so they never clash with FAILURE_EXIT_CODE
+const FATAL_EXIT_CODE_BASE: i32 = 100;
+
+impl ErrorCode {
+    pub const ALL: [ErrorCode; 27] = [
+        ErrorCode::CMakeVersion,
+        ErrorCode::CMakeInit,
+        ErrorCode::LayoutGeneration,
+        ErrorCode::CollectSources,
+        ErrorCode::DirectoryAccess,
+        ErrorCode::CMakeListsWrite,
+        ErrorCode::CMakeListsRead,
+        ErrorCode::ManifestRead,
+        ErrorCode::ManifestWrite,
+        ErrorCode::Profile,
+        ErrorCode::Target,
+        ErrorCode::Sanitizer,
+        ErrorCode::Toolchain,
+        ErrorCode::CompileCommands,
+        ErrorCode::Coverage,
+        ErrorCode::BuildCommand,
+        ErrorCode::RunExecutable,
+        ErrorCode::TestCommand,
+        ErrorCode::ProjectName,
+        ErrorCode::ForeignTarget,
+        ErrorCode::Explain,
+        ErrorCode::AddSource,
+        ErrorCode::RemoveSource,
+        ErrorCode::Install,
+        ErrorCode::Package,
+        ErrorCode::Warnings,
+        ErrorCode::NotImplemented,
+    ];
+
+    pub fn exit_code(self) -> i32 {
+        FATAL_EXIT_CODE_BASE + self as i32
+    }
+
+    // Extended help printed by `cbake explain`
+    pub fn explanation(self) -> &'static str {
+        use ErrorCode::*;
+        match self {
+            CMakeVersion => {
+                "cbake runs `cmake --version` to choose the CMakeLists.txt dialect, and the command failed \
+                 or printed something unexpected. Make sure cmake is installed and present in PATH. \
+                 Another cmake binary is used with --cmake <path> or the CBAKE_CMAKE environment variable"
+            }
+            CMakeInit => {
+                "cmake failed to configure the build directory. The cmake output above usually names the \
+                 problem: a syntax error in CMakeLists.txt, a missing compiler or a broken toolchain file. \
+                 Removing the build directory discards a stale cmake cache"
+            }
+            LayoutGeneration => {
+                "cbake could not create the directories and files of a new project. The project directory \
+                 must not exist yet and its parent directory must be writable"
+            }
+            CollectSources => "cbake could not list source files of the project. Check permissions of the src directory",
+            DirectoryAccess => {
+                "The current directory is not accessible. It may have been removed or its permissions changed"
+            }
+            CMakeListsWrite => "cbake could not write CMakeLists.txt. Check that the project directory is writable",
+            CMakeListsRead => {
+                "cbake could not read CMakeLists.txt. Commands other than `new` look for the project root \
+                 in the current directory and its parents, the first one with a cbake.toml or a CMakeLists.txt \
+                 managed by cbake. Plain CMake projects are only found in the directory itself. Use -C <dir> to \
+                 start the search somewhere else or --manifest-path to point to the project directly"
+            }
+            ManifestRead => {
+                "cbake.toml could not be read or parsed. The manifest has a [package] section with the \
+                 project name, an optional [build] section with settings of every profile and optional \
+                 [profile.<name>] and [target.<triple>] sections. Unknown keys are rejected"
+            }
+            ManifestWrite => "cbake could not write cbake.toml. Check that the project directory is writable",
+            Profile => {
+                "The selected build profile is not defined or inherits from an unknown profile. Built-in \
+                 profiles are debug, release, relwithdebinfo and minsizerel, others are defined in cbake.toml"
+            }
+            Target => {
+                "The value of --target is not a target triple. A triple is arch-vendor-system(-abi), \
+                 e.g. aarch64-linux-gnu, arm-none-eabi or x86_64-w64-mingw32"
+            }
+            Sanitizer => {
+                "The value of --sanitize names an unknown sanitizer or a combination which can't be linked \
+                 together. Thread sanitizer excludes address, memory and leak sanitizers, memory sanitizer \
+                 excludes address and leak sanitizers"
+            }
+            Toolchain => {
+                "cbake could not write a cmake toolchain file for the target into the build directory"
+            }
+            CompileCommands => {
+                "cbake could not link or copy compile_commands.json from the build directory to the \
+                 project directory. Remove a stale compile_commands.json from the project directory"
+            }
+            Coverage => {
+                "Coverage reports are made with gcov for gcc builds and llvm-cov for clang builds. \
+                 The tool matching the compiler must be installed and the build directory writable"
+            }
+            BuildCommand => "`cmake --build` could not be started. Make sure cmake is present in PATH",
+            RunExecutable => {
+                "The built executable or the runner of the target could not be started. Check that the \
+                 build produced the executable and that the runner command exists"
+            }
+            TestCommand => "ctest could not be started. It is shipped with cmake and must be present in PATH",
+            ProjectName => {
+                "The project name goes to project() and cbake.toml as it is, so it may contain letters and \
+                 digits of any script, `_`, `.`, `+` and `-` and must start with a letter or `_`. The cmake \
+                 target and the build artifacts get a name with ASCII characters only, other characters \
+                 become `_` and names cmake reserves like `test` get a leading `_`. `cbake new` takes the \
+                 project name from the last component of the path and replaces characters which aren't \
+                 allowed with `_`, `--name <name>` sets another one. The name in the [package] section of \
+                 cbake.toml follows the same rules"
+            }
+            ForeignTarget => {
+                "An executable built for a foreign target can't be run directly. Set \
+                 CBAKE_TARGET_<TRIPLE>_RUNNER to a runner command like qemu-user or wine, \
+                 e.g. CBAKE_TARGET_AARCH64_LINUX_GNU_RUNNER=\"qemu-aarch64 -L /usr/aarch64-linux-gnu\""
+            }
+            Explain => "`cbake explain` accepts error codes printed by cbake, e.g. E0007",
+            AddSource => {
+                "`cbake add class <name>` creates a header and a source file of the class. The class name \
+                 and the namespace parts must be C++ identifiers and cbake never overwrites existing files"
+            }
+            RemoveSource => {
+                "`cbake rm <name>` removes the project sources named <name> with any extension, \
+                 e.g. Foo.h and Foo.cpp. No such file was found or it couldn't be removed"
+            }
+            Install => {
+                "The install step failed. Installing into system directories like /usr/local needs root \
+                 permissions, --prefix <dir> installs the project elsewhere"
+            }
+            Package => {
+                "cpack failed or could not be started. .deb packages need a maintainer, set it in cbake.toml: \
+                 maintainer = \"Name <email>\" in the [package] section along with version and description"
+            }
+            Warnings => {
+                "--warnings and the warnings key of the [build] section in cbake.toml accept none, default, \
+                 strict and pedantic. Add --deny-warnings or deny-warnings = true to treat warnings as errors"
+            }
+            NotImplemented => {
+                "The command is part of the command line interface but doesn't do anything yet. \
+                 `cbake new <path>` creates a new project"
+            }
+        }
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "E{:04}", *self as u32)
+    }
+}
+
+impl FromStr for ErrorCode {
+    type Err = ParseErrorCodeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let digits = s.trim();
+        let digits = digits
+            .strip_prefix('E')
+            .or_else(|| digits.strip_prefix('e'))
+            .unwrap_or(digits);
+
+        let number: u32 = digits.parse().map_err(|_| ParseErrorCodeError(s.to_string()))?;
+
+        ErrorCode::ALL
+            .iter()
+            .find(|code| **code as u32 == number)
+            .copied()
+            .ok_or_else(|| ParseErrorCodeError(s.to_string()))
+    }
+}
+
+#[derive(Debug)]
+pub struct ParseErrorCodeError(String);
+
+impl fmt::Display for ParseErrorCodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unknown error code `{}`", self.0)
+    }
+}
+
+impl Error for ParseErrorCodeError {
+    fn description(&self) -> &str {
+        "Unknown error code"
+    }
+}
+
 // Any error that prevents command execution
 #[derive(Debug)]
 pub struct FatalError {
+    code: ErrorCode,
     error: Box<dyn Error>,
     what: String,
     help: Option<String>,
 }
 
 impl FatalError {
-    pub fn new<S: AsRef<str>>(error: Box<dyn Error>, what: S) -> Self {
+    pub fn new<S: AsRef<str>>(code: ErrorCode, error: Box<dyn Error>, what: S) -> Self {
         FatalError {
+            code,
             error,
             what: String::from(what.as_ref()),
             help: None,
         }
     }
 
-    pub fn with_help<S: AsRef<str>>(error: Box<dyn Error>, what: S, help: S) -> Self {
+    pub fn with_help<S: AsRef<str>>(code: ErrorCode, error: Box<dyn Error>, what: S, help: S) -> Self {
         FatalError {
+            code,
             error,
             what: String::from(what.as_ref()),
             help: Some(String::from(help.as_ref())),
         }
     }
+
+    pub fn code(&self) -> ErrorCode {
+        self.code
+    }
 }
 
 impl fmt::Display for FatalError {
@@ -89,22 +525,34 @@ impl fmt::Display for FatalError {
         writeln!(
             f,
             "  {} {}({}) ",
-            Colour::Red.bold().paint("Error:"),
+            Colour::Red.bold().paint(format!("Error[{}]:", self.code)),
             self.what,
-            self.error.description()
+            self.error
         )?;
 
         if let Some(help) = &self.help {
             writeln!(f, "  {} {}", Colour::Yellow.bold().paint("Help:"), help)?;
         }
 
-        Ok(())
+        writeln!(f, "  Run `cbake explain {}` for more information", self.code)
+    }
+}
+
+impl Serialize for FatalError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("FatalError", 5)?;
+        state.serialize_field("code", &self.code.to_string())?;
+        state.serialize_field("exit_code", &self.code.exit_code())?;
+        state.serialize_field("what", &self.what)?;
+        state.serialize_field("help", &self.help)?;
+        state.serialize_field("cause", &self.error.to_string())?;
+        state.end()
     }
 }
 
 impl Error for FatalError {
-    fn description(&self) -> &str {
-        self.error.description()
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(self.error.as_ref())
     }
 }
 
@@ -141,11 +589,20 @@ impl fmt::Display for ExecutionError {
 }
 
 impl Error for ExecutionError {
-    fn description(&self) -> &str {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
         use ExecutionError::*;
         match self {
-            Fatal(err) => err.description(),
-            Failure(err) => err.description(),
+            Fatal(err) => Some(err),
+            Failure(err) => Some(err),
+        }
+    }
+}
+
+impl ExecutionError {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ExecutionError::Fatal(err) => err.code.exit_code(),
+            ExecutionError::Failure(_) => FAILURE_EXIT_CODE,
         }
     }
 }
@@ -161,3 +618,34 @@ impl From<Failure> for ExecutionError {
         ExecutionError::Failure(err)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_codes() {
+        assert_eq!(ErrorCode::LayoutGeneration.to_string(), "E0003");
+        assert_eq!("E0003".parse::<ErrorCode>().unwrap(), ErrorCode::LayoutGeneration);
+        assert_eq!("e7".parse::<ErrorCode>().unwrap(), ErrorCode::CMakeListsRead);
+        assert!("E0000".parse::<ErrorCode>().is_err());
+        assert!("E9999".parse::<ErrorCode>().is_err());
+        assert!("foo".parse::<ErrorCode>().is_err());
+
+        for (i, code) in ErrorCode::ALL.iter().enumerate() {
+            assert_eq!(*code as usize, i + 1);
+            assert_eq!(code.to_string().parse::<ErrorCode>().unwrap(), *code);
+            assert!(code.exit_code() > FAILURE_EXIT_CODE && code.exit_code() < 256);
+            assert!(!code.explanation().is_empty());
+        }
+    }
+
+    #[test]
+    fn exit_codes() {
+        let fatal: ExecutionError = init_err(Failure).into();
+        assert_eq!(fatal.exit_code(), 102);
+
+        let failure: ExecutionError = Failure.into();
+        assert_eq!(failure.exit_code(), FAILURE_EXIT_CODE);
+    }
+}
@@ -0,0 +1,46 @@
+//! cbake generates CMakeLists.txt files, keeps their source lists up to date and drives cmake
+//! to build, run, test, install and package C++ projects.
+//!
+//! [`Project`] is the entry point for tools and build scripts. Generated CMake code is available
+//! through [`Builder`] and a [`Generator`] made by [`from_version`] without touching the file system,
+//! [`exec`] runs cmake, ctest and cpack directly. [`Simple`] is the [`ProjectLayout`] of cbake
+//! projects, it finds their sources and reads and writes files relative to the project root.
+
+mod arg_parser;
+pub(crate) mod cmake;
+pub(crate) mod commands;
+pub(crate) mod coverage;
+pub(crate) mod diagnostics;
+pub(crate) mod doctor;
+pub(crate) mod error;
+pub(crate) mod manifest;
+pub(crate) mod message;
+pub(crate) mod project_layout;
+pub(crate) mod project_name;
+pub(crate) mod sanitizer;
+pub(crate) mod templates;
+pub(crate) mod warnings;
+pub(crate) mod watch;
+
+mod project;
+
+#[cfg(test)]
+mod scratch;
+
+// The cbake binary, not a part of the library API
+#[doc(hidden)]
+pub mod cli;
+
+pub use cmake::{
+    exec,
+    generator::{from_version, Generator, PkgConfig, TargetSettings},
+    toolchain::Target,
+    Builder, Version,
+};
+pub use commands::BuildOptions;
+pub use error::{ErrorCode, ExecutionError, FatalError};
+pub use manifest::ProjectKind;
+pub use project::Project;
+pub use project_layout::{ProjectLayout, Simple};
+pub use sanitizer::SanitizerSet;
+pub use warnings::{WarningLevel, WarningPolicy};
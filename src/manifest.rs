@@ -0,0 +1,369 @@
+/// This module reads cbake.toml, the project manifest
+use crate::warnings::WarningLevel;
+use serde::Deserialize;
+
+use std::{collections::BTreeMap, error::Error, fmt, io, str::FromStr};
+
+pub const MANIFEST_FILE: &str = "cbake.toml";
+
+// Profiles which are always available. Each one maps to a cmake build type
+const BUILTIN_PROFILES: [(&str, &str); 4] = [
+    ("debug", "Debug"),
+    ("release", "Release"),
+    ("relwithdebinfo", "RelWithDebInfo"),
+    ("minsizerel", "MinSizeRel"),
+];
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    #[serde(default)]
+    pub package: Package,
+
+    #[serde(default)]
+    pub build: BuildSettings,
+
+    #[serde(default, rename = "profile")]
+    profiles: BTreeMap<String, ProfileDef>,
+
+    #[serde(default, rename = "target")]
+    targets: BTreeMap<String, TargetConfig>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Package {
+    pub name: Option<String>,
+    #[serde(default)]
+    pub kind: ProjectKind,
+    // Metadata of packages made by `cbake package`
+    pub version: Option<String>,
+    pub description: Option<String>,
+    pub maintainer: Option<String>,
+    // Libraries also get a .pc file for pkg-config
+    #[serde(default, rename = "pkg-config")]
+    pub pkg_config: bool,
+}
+
+// Compile and link settings used by every profile
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct BuildSettings {
+    #[serde(default)]
+    pub definitions: Vec<String>,
+    #[serde(default)]
+    pub options: Vec<String>,
+    #[serde(default)]
+    pub link_options: Vec<String>,
+    #[serde(default)]
+    pub link_libraries: Vec<String>,
+    #[serde(default)]
+    pub warnings: WarningLevel,
+    #[serde(default)]
+    pub deny_warnings: bool,
+}
+
+// Settings of a cross-compilation target, written as [target.<triple>]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TargetConfig {
+    // Compilers used instead of <triple>-gcc and <triple>-g++, e.g. the clang wrappers of the Android NDK
+    pub cc: Option<String>,
+    pub cxx: Option<String>,
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProjectKind {
+    #[default]
+    Executable,
+    // Installed with CMake package config files so other projects can find_package() it
+    Library,
+}
+
+// Profile as it is written in the manifest
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+struct ProfileDef {
+    inherits: Option<String>,
+    #[serde(default)]
+    flags: Vec<String>,
+    #[serde(default)]
+    definitions: Vec<String>,
+    #[serde(default)]
+    link_options: Vec<String>,
+    #[serde(default)]
+    link_libraries: Vec<String>,
+    output_dir: Option<String>,
+}
+
+// Fully resolved build profile
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Profile {
+    pub name: String,
+    pub build_type: String,
+    pub flags: Vec<String>,
+    pub definitions: Vec<String>,
+    pub link_options: Vec<String>,
+    pub link_libraries: Vec<String>,
+    pub output_dir: String,
+}
+
+impl FromStr for Manifest {
+    type Err = ManifestError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let manifest: Manifest = toml::from_str(s)?;
+
+        for (name, def) in &manifest.profiles {
+            let is_builtin = BUILTIN_PROFILES.iter().any(|(builtin, _)| builtin == name);
+
+            if is_builtin && def.inherits.is_some() {
+                return Err(ManifestError::Profile(format!(
+                    "built-in profile `{}` cannot inherit another profile",
+                    name
+                )));
+            }
+        }
+
+        Ok(manifest)
+    }
+}
+
+impl Manifest {
+    pub fn from_reader<R: io::Read>(mut reader: R) -> Result<Self, ManifestError> {
+        let mut contents = String::new();
+        reader.read_to_string(&mut contents)?;
+
+        contents.parse()
+    }
+
+    pub fn new_project(project_name: &str, kind: ProjectKind) -> String {
+        let mut manifest = format!("[package]\nname = \"{}\"\nversion = \"0.1.0\"\n", project_name);
+
+        if kind == ProjectKind::Library {
+            manifest += "kind = \"library\"\n";
+        }
+
+        manifest
+    }
+
+    pub fn profile(&self, name: &str) -> Result<Profile, ManifestError> {
+        self.resolve_profile(name, &mut Vec::new())
+    }
+
+    // Targets which aren't in the manifest use the default settings
+    pub fn target(&self, triple: &str) -> TargetConfig {
+        self.targets.get(triple).cloned().unwrap_or_default()
+    }
+
+    pub fn profile_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = BUILTIN_PROFILES.iter().map(|(name, _)| name.to_string()).collect();
+
+        for name in self.profiles.keys() {
+            if !names.contains(name) {
+                names.push(name.clone());
+            }
+        }
+
+        names
+    }
+
+    fn resolve_profile(&self, name: &str, visited: &mut Vec<String>) -> Result<Profile, ManifestError> {
+        if visited.iter().any(|visited| visited == name) {
+            return Err(ManifestError::Profile(format!(
+                "profile `{}` inherits itself",
+                name
+            )));
+        }
+        visited.push(name.to_string());
+
+        let builtin = BUILTIN_PROFILES.iter().find(|(builtin, _)| *builtin == name);
+        let def = self.profiles.get(name);
+
+        let mut profile = match (builtin, def) {
+            (Some((name, build_type)), _) => Profile {
+                name: name.to_string(),
+                build_type: build_type.to_string(),
+                flags: Vec::new(),
+                definitions: Vec::new(),
+                link_options: Vec::new(),
+                link_libraries: Vec::new(),
+                output_dir: build_type.to_string(),
+            },
+            (None, Some(def)) => {
+                // Custom profiles are based on debug unless told otherwise
+                let base = def.inherits.as_deref().unwrap_or("debug");
+                let base = self.resolve_profile(base, visited)?;
+
+                Profile {
+                    name: name.to_string(),
+                    output_dir: name.to_string(),
+                    ..base
+                }
+            }
+            (None, None) => return Err(ManifestError::UnknownProfile(name.to_string())),
+        };
+
+        if let Some(def) = def {
+            profile.flags.extend(def.flags.iter().cloned());
+            profile.definitions.extend(def.definitions.iter().cloned());
+            profile.link_options.extend(def.link_options.iter().cloned());
+            profile.link_libraries.extend(def.link_libraries.iter().cloned());
+
+            if let Some(output_dir) = &def.output_dir {
+                profile.output_dir = output_dir.clone();
+            }
+        }
+
+        Ok(profile)
+    }
+}
+
+#[derive(Debug)]
+pub enum ManifestError {
+    IO(io::Error),
+    Parse(toml::de::Error),
+    Profile(String),
+    UnknownProfile(String),
+}
+
+impl fmt::Display for ManifestError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ManifestError::IO(err) => write!(f, "{}", err),
+            ManifestError::Parse(err) => write!(f, "{}", err),
+            ManifestError::Profile(msg) => write!(f, "{}", msg),
+            ManifestError::UnknownProfile(name) => write!(f, "no profile named `{}`", name),
+        }
+    }
+}
+
+impl Error for ManifestError {
+    fn description(&self) -> &str {
+        "Invalid manifest"
+    }
+}
+
+impl From<io::Error> for ManifestError {
+    fn from(err: io::Error) -> Self {
+        ManifestError::IO(err)
+    }
+}
+
+impl From<toml::de::Error> for ManifestError {
+    fn from(err: toml::de::Error) -> Self {
+        ManifestError::Parse(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_profiles() {
+        let manifest = Manifest::default();
+
+        let debug = manifest.profile("debug").unwrap();
+        assert_eq!(debug.build_type, "Debug");
+        assert_eq!(debug.output_dir, "Debug");
+
+        let release = manifest.profile("relwithdebinfo").unwrap();
+        assert_eq!(release.build_type, "RelWithDebInfo");
+
+        assert!(manifest.profile("asan").is_err());
+    }
+
+    #[test]
+    fn custom_profiles() {
+        let manifest = Manifest::from_str(
+            r#"
+            [package]
+            name = "demo"
+            version = "1.2.0"
+            maintainer = "Jane Doe <jane@example.com>"
+
+            [build]
+            definitions = ["USE_FOO"]
+            link-libraries = ["pthread"]
+            warnings = "pedantic"
+
+            [profile.release]
+            flags = ["-march=native"]
+            link-options = ["-s"]
+
+            [profile.profiling]
+            inherits = "release"
+            flags = ["-pg"]
+            definitions = ["PROFILING", "LEVEL=2"]
+
+            [profile.asan]
+            flags = ["-fsanitize=address"]
+            output-dir = "Asan"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(manifest.package.name.as_deref(), Some("demo"));
+        assert_eq!(manifest.package.version.as_deref(), Some("1.2.0"));
+        assert_eq!(manifest.package.description, None);
+        assert_eq!(manifest.package.kind, ProjectKind::Executable);
+        assert!(!manifest.package.pkg_config);
+        assert!(Manifest::from_str("[package]\npkg-config = true").unwrap().package.pkg_config);
+
+        let library = Manifest::from_str(&Manifest::new_project("demo", ProjectKind::Library)).unwrap();
+        assert_eq!(library.package.kind, ProjectKind::Library);
+        assert!(Manifest::from_str("[package]\nkind = \"plugin\"").is_err());
+
+        let profiling = manifest.profile("profiling").unwrap();
+        assert_eq!(profiling.build_type, "Release");
+        assert_eq!(profiling.output_dir, "profiling");
+        assert_eq!(profiling.flags, vec!["-march=native", "-pg"]);
+        assert_eq!(profiling.definitions, vec!["PROFILING", "LEVEL=2"]);
+        assert_eq!(profiling.link_options, vec!["-s"]);
+
+        assert_eq!(manifest.build.definitions, vec!["USE_FOO"]);
+        assert_eq!(manifest.build.link_libraries, vec!["pthread"]);
+        assert!(manifest.build.options.is_empty());
+        assert_eq!(manifest.build.warnings, WarningLevel::Pedantic);
+        assert!(!manifest.build.deny_warnings);
+        assert!(Manifest::from_str("[build]\nwarnings = \"loud\"").is_err());
+
+        let asan = manifest.profile("asan").unwrap();
+        assert_eq!(asan.build_type, "Debug");
+        assert_eq!(asan.output_dir, "Asan");
+
+        assert_eq!(
+            manifest.profile_names(),
+            vec!["debug", "release", "relwithdebinfo", "minsizerel", "asan", "profiling"]
+        );
+    }
+
+    #[test]
+    fn invalid_profiles() {
+        let cyclic = Manifest::from_str("[profile.a]\ninherits = \"b\"\n[profile.b]\ninherits = \"a\"\n").unwrap();
+        assert!(cyclic.profile("a").is_err());
+
+        let missing = Manifest::from_str("[profile.a]\ninherits = \"b\"\n").unwrap();
+        assert!(missing.profile("a").is_err());
+
+        assert!(Manifest::from_str("[profile.debug]\ninherits = \"release\"\n").is_err());
+        assert!(Manifest::from_str("[profile.debug]\nunknown = 1\n").is_err());
+    }
+
+    #[test]
+    fn targets() {
+        let manifest = Manifest::from_str(
+            "[target.aarch64-linux-android]\ncc = \"aarch64-linux-android21-clang\"\ncxx = \"aarch64-linux-android21-clang++\"\n",
+        )
+        .unwrap();
+
+        let android = manifest.target("aarch64-linux-android");
+        assert_eq!(android.cc.as_deref(), Some("aarch64-linux-android21-clang"));
+        assert_eq!(android.cxx.as_deref(), Some("aarch64-linux-android21-clang++"));
+        assert_eq!(manifest.target("arm-none-eabi"), TargetConfig::default());
+
+        assert!(Manifest::from_str("[target.arm-none-eabi]\nlinker = \"ld\"\n").is_err());
+    }
+}
@@ -0,0 +1,390 @@
+/// This module prints messages either for humans or as newline-delimited JSON
+use crate::diagnostics::{Diagnostic, Summary};
+use crate::doctor::{Check, CheckStatus};
+use crate::error::FatalError;
+use crate::sanitizer::Report;
+
+use ansi_term::Colour;
+use serde::Serialize;
+
+use std::{
+    cell::RefCell,
+    error::Error,
+    fmt,
+    path::Path,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MessageFormat {
+    Human,
+    Json,
+}
+
+impl FromStr for MessageFormat {
+    type Err = ParseMessageFormatError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "human" => Ok(MessageFormat::Human),
+            "json" => Ok(MessageFormat::Json),
+            _ => Err(ParseMessageFormatError),
+        }
+    }
+}
+
+// The format is chosen once on startup, so it is kept globally instead of being passed everywhere
+static JSON: AtomicBool = AtomicBool::new(false);
+
+pub fn set_format(format: MessageFormat) {
+    JSON.store(format == MessageFormat::Json, Ordering::Relaxed);
+}
+
+pub fn is_json() -> bool {
+    JSON.load(Ordering::Relaxed) || SINK.with(|sink| sink.borrow().is_some())
+}
+
+pub type Sink = Arc<dyn Fn(&str) + Send + Sync>;
+
+thread_local! {
+    // Library callers get the messages of a command as JSON lines instead of stdout
+    static SINK: RefCell<Option<Sink>> = RefCell::new(None);
+}
+
+// Passes messages emitted by `f` to `sink`. Tool output goes to stderr then, as in JSON mode
+pub fn with_sink<T>(sink: Sink, f: impl FnOnce() -> T) -> T {
+    let _guard = SinkGuard {
+        previous: SINK.with(|current| current.replace(Some(sink))),
+    };
+
+    f()
+}
+
+// Restores the previous sink even if the command panics
+struct SinkGuard {
+    previous: Option<Sink>,
+}
+
+impl Drop for SinkGuard {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        SINK.with(|current| *current.borrow_mut() = previous);
+    }
+}
+
+// Prints a line of a tool's output (cmake, ctest). In JSON mode stdout is reserved for messages
+pub fn tool_output(line: &str) {
+    if is_json() {
+        eprintln!("{}", line);
+    } else {
+        println!("{}", line);
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct CoverageFile {
+    pub file: String,
+    pub covered: usize,
+    pub total: usize,
+    pub percent: f64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "reason", rename_all = "kebab-case")]
+pub enum Message<'a> {
+    Status {
+        action: &'a str,
+        subject: &'a str,
+        details: Option<&'a str>,
+    },
+    ConfigureStarted {
+        build_dir: &'a Path,
+    },
+    ConfigureFinished {
+        build_dir: &'a Path,
+        success: bool,
+    },
+    Diagnostic {
+        #[serde(flatten)]
+        diagnostic: &'a Diagnostic,
+        #[serde(skip)]
+        project_root: &'a Path,
+    },
+    // Output of a failed build which has no diagnostics, like linker errors
+    BuildOutput {
+        lines: &'a [String],
+    },
+    BuildFinished {
+        success: bool,
+        #[serde(flatten)]
+        summary: Summary,
+    },
+    TargetBuilt {
+        name: &'a str,
+        artifact: &'a Path,
+    },
+    PackageCreated {
+        file: &'a Path,
+    },
+    TestResult {
+        name: &'a str,
+        success: bool,
+        duration: Option<f64>,
+    },
+    SanitizerReports {
+        reports: &'a [Report],
+    },
+    Coverage {
+        files: &'a [CoverageFile],
+        covered: usize,
+        total: usize,
+        percent: f64,
+        lcov: &'a Path,
+        html: &'a Path,
+    },
+    Check {
+        #[serde(flatten)]
+        check: &'a Check,
+    },
+    Explanation {
+        code: &'a str,
+        explanation: &'a str,
+        exit_code: i32,
+    },
+    // Problems which don't stop the command
+    Warning {
+        message: &'a str,
+    },
+    Error {
+        #[serde(flatten)]
+        error: &'a FatalError,
+    },
+    // Shown to humans only, JSON consumers get the success flag from other messages
+    #[serde(skip)]
+    Success,
+    #[serde(skip)]
+    Failure,
+    Finished {
+        success: bool,
+    },
+}
+
+pub fn emit(message: Message) {
+    if let Some(sink) = SINK.with(|sink| sink.borrow().clone()) {
+        if let Ok(json) = serde_json::to_string(&message) {
+            sink(&json);
+        }
+    } else if is_json() {
+        if let Ok(json) = serde_json::to_string(&message) {
+            println!("{}", json);
+        }
+    } else {
+        print_human(&message);
+    }
+}
+
+fn print_human(message: &Message) {
+    match message {
+        Message::Status {
+            action,
+            subject,
+            details,
+        } => match details {
+            Some(details) => println!(
+                "  {} {} ({})",
+                Colour::Green.bold().paint(*action),
+                Colour::White.bold().paint(*subject),
+                details
+            ),
+            None => println!(
+                "  {} {}",
+                Colour::Green.bold().paint(*action),
+                Colour::White.bold().paint(*subject)
+            ),
+        },
+        Message::Diagnostic {
+            diagnostic,
+            project_root,
+        } => eprintln!("{}", diagnostic.render(project_root)),
+        Message::BuildOutput { lines } => {
+            for line in lines.iter() {
+                eprintln!("{}", line);
+            }
+        }
+        Message::BuildFinished { summary, .. } => {
+            if summary.errors > 0 || summary.warnings > 0 {
+                println!("  {} {}", Colour::Yellow.bold().paint("Diagnostics"), summary);
+            }
+        }
+        Message::SanitizerReports { reports } => {
+            if !reports.is_empty() {
+                println!(
+                    "  {} found {} issue(s):",
+                    Colour::Red.bold().paint("Sanitizers"),
+                    reports.len()
+                );
+
+                for report in reports.iter() {
+                    println!("    {}: {}", Colour::White.bold().paint(&report.tool), report.message);
+                }
+            }
+        }
+        Message::Coverage {
+            files,
+            covered,
+            total,
+            percent,
+            lcov,
+            html,
+        } => {
+            println!("  {}", Colour::Green.bold().paint("Coverage"));
+
+            for file in files.iter() {
+                println!(
+                    "    {:>6.1}%  {:>5}/{:<5} {}",
+                    file.percent, file.covered, file.total, file.file
+                );
+            }
+
+            println!(
+                "    {:>6.1}%  {:>5}/{:<5} {}",
+                percent,
+                covered,
+                total,
+                Colour::White.bold().paint("Total")
+            );
+            println!("    Reports: {} and {}", lcov.display(), html.display());
+        }
+        Message::Check { check } => {
+            let status = match check.status {
+                CheckStatus::Ok => Colour::Green.bold().paint("    ok"),
+                CheckStatus::Warning => Colour::Yellow.bold().paint("  warn"),
+                CheckStatus::Error => Colour::Red.bold().paint(" error"),
+            };
+
+            println!("{} {}: {}", status, Colour::White.bold().paint(&check.name), check.details);
+
+            if let Some(help) = &check.help {
+                println!("        {} {}", Colour::Yellow.bold().paint("Help:"), help);
+            }
+        }
+        Message::PackageCreated { file } => println!(
+            "  {} {}",
+            Colour::Green.bold().paint("Packaged"),
+            Colour::White.bold().paint(file.display().to_string())
+        ),
+        Message::Explanation {
+            code,
+            explanation,
+            exit_code,
+        } => {
+            println!("{}\n", Colour::White.bold().paint(*code));
+            println!("{}\n", explanation);
+            println!("Exit status: {}", exit_code);
+        }
+        Message::Warning { message } => eprintln!("{}: {}", Colour::Yellow.bold().paint("warning"), message),
+        Message::Error { error } => eprintln!("{}", error),
+        Message::Success => println!("  {}", Colour::Green.bold().paint("Success")),
+        Message::Failure => eprintln!("  {}", Colour::Red.bold().paint("Failure")),
+        Message::ConfigureStarted { .. }
+        | Message::ConfigureFinished { .. }
+        | Message::TargetBuilt { .. }
+        | Message::TestResult { .. }
+        | Message::Finished { .. } => {}
+    }
+}
+
+#[derive(Debug)]
+pub struct ParseMessageFormatError;
+
+impl fmt::Display for ParseMessageFormatError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "message format must be either human or json")
+    }
+}
+
+impl Error for ParseMessageFormatError {
+    fn description(&self) -> &str {
+        "message format must be either human or json"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostics::Severity;
+
+    #[test]
+    fn json_messages() {
+        let status = Message::Status {
+            action: "Building",
+            subject: "demo",
+            details: Some("debug"),
+        };
+        assert_eq!(
+            serde_json::to_string(&status).unwrap(),
+            r#"{"reason":"status","action":"Building","subject":"demo","details":"debug"}"#
+        );
+
+        let diagnostic = Diagnostic {
+            severity: Severity::Warning,
+            file: String::from("/demo/main.cpp"),
+            line: 4,
+            column: Some(7),
+            message: String::from("unused variable 'x'"),
+        };
+        let message = Message::Diagnostic {
+            diagnostic: &diagnostic,
+            project_root: Path::new("/demo"),
+        };
+        assert_eq!(
+            serde_json::to_string(&message).unwrap(),
+            r#"{"reason":"diagnostic","severity":"warning","file":"/demo/main.cpp","line":4,"column":7,"message":"unused variable 'x'"}"#
+        );
+
+        let finished = Message::BuildFinished {
+            success: false,
+            summary: Summary { errors: 1, warnings: 2 },
+        };
+        assert_eq!(
+            serde_json::to_string(&finished).unwrap(),
+            r#"{"reason":"build-finished","success":false,"errors":1,"warnings":2}"#
+        );
+
+        let lines = vec![String::from("collect2: error: ld returned 1 exit status")];
+        assert_eq!(
+            serde_json::to_string(&Message::BuildOutput { lines: &lines }).unwrap(),
+            r#"{"reason":"build-output","lines":["collect2: error: ld returned 1 exit status"]}"#
+        );
+
+        assert!(serde_json::to_string(&Message::Success).is_err());
+    }
+
+    #[test]
+    fn sink() {
+        let lines = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink_lines = Arc::clone(&lines);
+
+        let json = with_sink(Arc::new(move |line: &str| sink_lines.lock().unwrap().push(line.to_string())), || {
+            emit(Message::Finished { success: true });
+            emit(Message::Success);
+            is_json()
+        });
+
+        assert!(json);
+        assert!(!SINK.with(|sink| sink.borrow().is_some()));
+        assert_eq!(*lines.lock().unwrap(), vec![r#"{"reason":"finished","success":true}"#]);
+    }
+
+    #[test]
+    fn sink_after_panic() {
+        let result = std::panic::catch_unwind(|| with_sink(Arc::new(|_: &str| {}), || panic!("command failed")));
+
+        assert!(result.is_err());
+        assert!(!SINK.with(|sink| sink.borrow().is_some()));
+    }
+}
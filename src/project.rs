@@ -0,0 +1,189 @@
+/// This module provides a handle to a cbake project for programs using cbake as a library
+use crate::commands::{self, BuildOptions, ExecutionResult};
+use crate::error::{self, ExecutionError, FatalError};
+use crate::manifest::ProjectKind;
+use crate::message::{self, Sink};
+use crate::project_layout;
+
+use std::{
+    fmt,
+    fs::File,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+// A project directory with a CMakeLists.txt. Commands don't print their messages, they are passed
+// to the sink given with `on_message` instead
+#[derive(Clone)]
+pub struct Project {
+    root: PathBuf,
+    sink: Option<Sink>,
+}
+
+impl fmt::Debug for Project {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Project")
+            .field("root", &self.root)
+            .field("sink", &self.sink.is_some())
+            .finish()
+    }
+}
+
+impl Project {
+    // Opens an existing project, fails when there's no CMakeLists.txt in the directory
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, FatalError> {
+        let root = path.as_ref().to_path_buf();
+        File::open(root.join("CMakeLists.txt")).map_err(error::cmake_read_err)?;
+
+        Ok(Project { root, sink: None })
+    }
+
+    // Opens the project `dir` belongs to, looking for its root in the parent directories
+    pub fn discover<P: AsRef<Path>>(dir: P) -> Result<Self, FatalError> {
+        let dir = dir.as_ref();
+        Project::open(project_layout::find_project_root(dir).as_deref().unwrap_or(dir))
+    }
+
+    // Creates a new project in a directory which must not exist yet, named after the directory
+    pub fn new<P: AsRef<Path>>(path: P, kind: ProjectKind) -> Result<Self, ExecutionError> {
+        message::with_sink(Arc::new(|_: &str| {}), || commands::new(path.as_ref(), kind, None))?;
+        Ok(Project::open(path)?)
+    }
+
+    // Messages are the JSON lines printed by `cbake --message-format json`
+    pub fn on_message<F>(mut self, sink: F) -> Self
+    where
+        F: Fn(&str) + Send + Sync + 'static,
+    {
+        self.sink = Some(Arc::new(sink));
+        self
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn build(&self, options: &BuildOptions) -> ExecutionResult {
+        self.with_sink(|| commands::build(&self.root, options))
+    }
+
+    pub fn run(&self, options: &BuildOptions) -> ExecutionResult {
+        self.with_sink(|| commands::run(&self.root, options))
+    }
+
+    pub fn test(&self, options: &BuildOptions) -> ExecutionResult {
+        self.with_sink(|| commands::test(&self.root, options))
+    }
+
+    pub fn clean(&self) -> ExecutionResult {
+        self.with_sink(|| commands::clean(&self.root))
+    }
+
+    // Without a sink the messages are dropped
+    fn with_sink<T>(&self, f: impl FnOnce() -> T) -> T {
+        let sink = self.sink.clone().unwrap_or_else(|| Arc::new(|_: &str| {}));
+        message::with_sink(sink, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorCode;
+    use crate::scratch::ScratchDir;
+    use std::sync::Mutex;
+
+    #[test]
+    fn open_requires_cmake_lists() {
+        let scratch = ScratchDir::new("project");
+        let dir = scratch.path();
+
+        let err = Project::open(dir).unwrap_err();
+        assert_eq!(err.code(), ErrorCode::CMakeListsRead);
+
+        std::fs::write(dir.join("CMakeLists.txt"), "project(demo)\n").unwrap();
+        let project = Project::open(dir).unwrap();
+        assert_eq!(project.root(), dir);
+
+        // A plain CMakeLists.txt is only found in the directory itself
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        assert!(Project::discover(dir.join("src")).is_err());
+        std::fs::write(dir.join("cbake.toml"), "").unwrap();
+        assert_eq!(Project::discover(dir.join("src")).unwrap().root(), dir);
+    }
+
+    #[test]
+    fn messages_go_to_the_sink() {
+        let scratch = ScratchDir::new("project-sink");
+        let dir = scratch.path();
+        std::fs::create_dir_all(dir.join("build")).unwrap();
+        std::fs::write(dir.join("CMakeLists.txt"), "project(demo)\n").unwrap();
+
+        let lines = Arc::new(Mutex::new(Vec::new()));
+        let sink_lines = Arc::clone(&lines);
+        let project = Project::open(dir)
+            .unwrap()
+            .on_message(move |line| sink_lines.lock().unwrap().push(line.to_string()));
+
+        project.clean().unwrap();
+        assert!(!dir.join("build").exists());
+
+        let lines = lines.lock().unwrap();
+        assert!(!lines.is_empty());
+        assert!(lines.iter().all(|line| line.starts_with(r#"{"reason":"#)));
+    }
+
+    // Set for the copy of the test binary which runs `failing_build_child`
+    #[cfg(unix)]
+    const CHILD_VAR: &str = "CBAKE_TEST_FAILING_BUILD";
+
+    // Runs the failing build in a child process, so anything it prints itself can be seen
+    #[cfg(unix)]
+    #[test]
+    fn failing_build_output_goes_to_the_sink() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = ScratchDir::new("project-failing");
+
+        // The link step fails with output which isn't a diagnostic
+        let cmake = dir.join("cmake");
+        std::fs::write(
+            &cmake,
+            "#!/bin/sh\ncase \"$1\" in\n--version) echo \"cmake version 3.16.3\" ;;\n--build) echo \"collect2: error: ld returned 1 exit status\"; exit 1 ;;\nesac\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&cmake, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let output = std::process::Command::new(std::env::current_exe().unwrap())
+            .args(["project::tests::failing_build_child", "--exact", "--nocapture"])
+            .env(CHILD_VAR, dir.join("demo"))
+            .env(crate::cmake::exec::CMAKE_ENV_VAR, &cmake)
+            .output()
+            .unwrap();
+
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stdout));
+        assert_eq!(String::from_utf8_lossy(&output.stderr), "");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn failing_build_child() {
+        let dir = match std::env::var_os(CHILD_VAR) {
+            Some(dir) => dir,
+            None => return,
+        };
+
+        let lines = Arc::new(Mutex::new(Vec::new()));
+        let sink_lines = Arc::clone(&lines);
+        let project = Project::new(&dir, ProjectKind::Executable)
+            .unwrap()
+            .on_message(move |line| sink_lines.lock().unwrap().push(line.to_string()));
+
+        assert!(project.build(&BuildOptions::default()).is_err());
+
+        let lines = lines.lock().unwrap();
+        assert!(lines.contains(&String::from(
+            r#"{"reason":"build-output","lines":["collect2: error: ld returned 1 exit status"]}"#
+        )));
+    }
+}
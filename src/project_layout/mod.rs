@@ -2,14 +2,21 @@ pub mod simple;
 
 pub use simple::Simple;
 
-use std::{fs, io, path::PathBuf};
+use crate::{
+    cmake::builder,
+    manifest::{self, ProjectKind},
+};
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
 
 const MAIN_CPP_CONTENTS: &str = include_str!("../../resources/main.cpp");
 
 pub trait ProjectLayout {
     // Generate project files
     fn generate(&self) -> io::Result<()>;
-    fn collect_include_dirs(&self) -> io::Result<Vec<String>>;
     fn collect_sources(&self) -> io::Result<Vec<String>>;
 
     fn get_build_path(&self) -> PathBuf;
@@ -22,6 +29,50 @@ pub trait ProjectLayout {
 
     fn open_file(&self, file_name: &str) -> io::Result<fs::File>;
 
+    fn remove_file(&mut self, file_name: &str) -> io::Result<()>;
+
+    // Must return paths of the header and the source file of a new class
+    fn class_files(&self, class_name: &str, kind: ProjectKind) -> (String, String);
+
     // Add custom directory to layout
     fn create_dir(&mut self, dir_name: &str) -> io::Result<()>;
 }
+
+// Walks up from `dir` to the first directory with cbake.toml or a CMakeLists.txt generated by cbake,
+// so commands work from any subdirectory of a project. CMakeLists.txt of subprojects is skipped
+pub fn find_project_root(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .find(|dir| {
+            dir.join(manifest::MANIFEST_FILE).is_file()
+                || fs::read_to_string(dir.join("CMakeLists.txt"))
+                    .map(|cmake_lists| builder::is_managed(&cmake_lists))
+                    .unwrap_or(false)
+        })
+        .map(Path::to_path_buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scratch::ScratchDir;
+
+    #[test]
+    fn project_root() {
+        let scratch = ScratchDir::new("root");
+        let root = scratch.path().to_path_buf();
+        let nested = root.join("src/detail");
+        fs::create_dir_all(&nested).unwrap();
+
+        // A hand-written CMakeLists.txt doesn't make a project root
+        fs::write(root.join("src/CMakeLists.txt"), "add_library(detail detail.cpp)\n").unwrap();
+        assert_eq!(find_project_root(&nested), None);
+
+        let managed = format!("project(demo)\n#{}\nadd_executable(demo main.cpp)\n", builder::SOURCES_SECTION_MARKER);
+        fs::write(root.join("CMakeLists.txt"), managed).unwrap();
+        assert_eq!(find_project_root(&nested), Some(root.clone()));
+
+        fs::write(root.join("src").join(manifest::MANIFEST_FILE), "[package]\n").unwrap();
+        assert_eq!(find_project_root(&nested), Some(root.join("src")));
+        assert_eq!(find_project_root(&root), Some(root.clone()));
+    }
+}
@@ -8,6 +8,7 @@
 
 use super::ProjectLayout;
 use super::MAIN_CPP_CONTENTS;
+use crate::manifest::ProjectKind;
 
 use std::{
     fs,
@@ -29,30 +30,33 @@ impl Simple {
     }
 }
 
+impl Simple {
+    // Layout operations never change the working directory, paths are joined onto the project root
+    fn path(&self, file_name: &str) -> PathBuf {
+        self.project_root.join(file_name)
+    }
+}
+
 impl ProjectLayout for Simple {
     fn generate(&self) -> io::Result<()> {
         fs::create_dir(&self.project_root)?;
-        let _dir_guard = change_dir(&self.project_root)?;
 
-        fs::create_dir_all(DEBUG_TARGET_PATH)?;
-        fs::create_dir_all(RELEASE_TARGET_PATH)?;
+        fs::create_dir_all(self.path(DEBUG_TARGET_PATH))?;
+        fs::create_dir_all(self.path(RELEASE_TARGET_PATH))?;
 
-        let mut f = fs::File::create("main.cpp")?;
+        let mut f = fs::File::create(self.path("main.cpp"))?;
 
-        f.write(MAIN_CPP_CONTENTS.as_bytes())?;
+        f.write_all(MAIN_CPP_CONTENTS.as_bytes())?;
 
         Ok(())
     }
 
-    fn collect_include_dirs(&self) -> io::Result<Vec<String>> {
-        Ok(Vec::new())
-    }
-
+    // Sources are relative to the project root. Files whose names aren't valid UTF-8 can't be
+    // written into CMakeLists.txt and are skipped
     fn collect_sources(&self) -> io::Result<Vec<String>> {
         let mut sources = Vec::new();
-        let _dir_guard = change_dir(&self.project_root)?;
 
-        for entry in fs::read_dir(".")? {
+        for entry in fs::read_dir(&self.project_root)? {
             let entry = entry?;
 
             let entry_path = entry.path();
@@ -63,7 +67,9 @@ impl ProjectLayout for Simple {
                         || extension == "hpp"
                         || extension == "c"
                     {
-                        sources.push(entry_path.into_os_string().into_string().unwrap());
+                        if let Ok(file_name) = entry.file_name().into_string() {
+                            sources.push(file_name);
+                        }
                     }
                 }
             }
@@ -84,43 +90,94 @@ impl ProjectLayout for Simple {
     }
 
     fn write_file(&mut self, file_name: &str, contents: &[u8]) -> io::Result<()> {
-        let _dir_guard = change_dir(&self.project_root)?;
-
-        let mut f = fs::File::create(file_name)?;
-        f.write(contents)?;
+        let mut f = fs::File::create(self.path(file_name))?;
+        f.write_all(contents)?;
 
         Ok(())
     }
 
     fn open_file(&self, file_name: &str) -> io::Result<fs::File> {
-        let _dir_guard = change_dir(&self.project_root)?;
+        fs::File::open(self.path(file_name))
+    }
 
-        fs::File::open(file_name)
+    fn remove_file(&mut self, file_name: &str) -> io::Result<()> {
+        fs::remove_file(self.path(file_name))
     }
 
-    fn create_dir(&mut self, dir_name: &str) -> io::Result<()> {
-        let _dir_guard = change_dir(&self.project_root)?;
+    // Headers of libraries are public and go to the installed include directory
+    fn class_files(&self, class_name: &str, kind: ProjectKind) -> (String, String) {
+        let header = match kind {
+            ProjectKind::Executable => format!("{}.h", class_name),
+            ProjectKind::Library => format!("include/{}.h", class_name),
+        };
+
+        (header, format!("{}.cpp", class_name))
+    }
 
-        fs::create_dir(dir_name)?;
+    fn create_dir(&mut self, dir_name: &str) -> io::Result<()> {
+        fs::create_dir(self.path(dir_name))?;
 
         Ok(())
     }
 }
 
-// This type allows to change working directory temporary until instance of this type exists
-struct ChangeDirGuard {
-    prev_dir: PathBuf,
-}
-
-impl Drop for ChangeDirGuard {
-    fn drop(&mut self) {
-        let _ = std::env::set_current_dir(&self.prev_dir);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scratch::ScratchDir;
+
+    // Tests run in parallel, so each one gets its own project and the working directory stays untouched
+    #[test]
+    fn files_relative_to_root() {
+        let working_dir = std::env::current_dir().unwrap();
+        let scratch = ScratchDir::new("layout");
+        // generate() creates the project directory itself
+        let root = scratch.join("demo");
+
+        let mut layout = Simple::new(root.clone());
+        layout.generate().unwrap();
+        layout.write_file("util.h", b"#pragma once\n").unwrap();
+        layout.write_file("notes.txt", b"").unwrap();
+        layout.create_dir("include").unwrap();
+
+        let mut sources = layout.collect_sources().unwrap();
+        sources.sort();
+        assert_eq!(sources, vec!["main.cpp", "util.h"]);
+
+        assert!(root.join("build/Debug").is_dir());
+        assert!(root.join("include").is_dir());
+        assert!(layout.open_file("util.h").is_ok());
+
+        layout.remove_file("util.h").unwrap();
+        assert!(layout.open_file("util.h").is_err());
+        assert_eq!(std::env::current_dir().unwrap(), working_dir);
     }
-}
 
-fn change_dir(new_dir: &PathBuf) -> io::Result<ChangeDirGuard> {
-    let prev_dir = std::env::current_dir()?;
-    std::env::set_current_dir(new_dir)?;
+    #[cfg(unix)]
+    #[test]
+    fn non_utf8_file_names() {
+        use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
 
-    Ok(ChangeDirGuard { prev_dir })
+        let root = ScratchDir::new("layout-non-utf8");
+
+        fs::write(root.join("main.cpp"), "").unwrap();
+        fs::write(root.join(OsStr::from_bytes(b"bad\xff.cpp")), "").unwrap();
+
+        let layout = Simple::new(root.path().to_path_buf());
+        assert_eq!(layout.collect_sources().unwrap(), vec!["main.cpp"]);
+    }
+
+    #[test]
+    fn class_files() {
+        let layout = Simple::new(PathBuf::from("demo"));
+
+        assert_eq!(
+            layout.class_files("Parser", ProjectKind::Executable),
+            (String::from("Parser.h"), String::from("Parser.cpp"))
+        );
+        assert_eq!(
+            layout.class_files("Parser", ProjectKind::Library),
+            (String::from("include/Parser.h"), String::from("Parser.cpp"))
+        );
+    }
 }
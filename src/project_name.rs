@@ -0,0 +1,170 @@
+/// This module checks project names and derives CMake target names from them
+use std::{error::Error, fmt};
+
+// Names CMake reserves for its own targets (policy CMP0037)
+const RESERVED_NAMES: &[&str] = &[
+    "all",
+    "clean",
+    "help",
+    "install",
+    "package",
+    "package_source",
+    "test",
+    "edit_cache",
+    "rebuild_cache",
+    "ALL_BUILD",
+    "ZERO_CHECK",
+    "RUN_TESTS",
+    "INSTALL",
+    "PACKAGE",
+];
+
+// The project name goes to project() and cbake.toml as it is, so it may contain letters and digits
+// of any script but no characters with a meaning for CMake or the shell. It must also start like an
+// identifier, names like `2048` or `-foo` are taken for numbers and options by cmake and other tools
+pub fn validate(name: &str) -> Result<(), InvalidProjectName> {
+    let first = name.chars().next().ok_or(InvalidProjectName::Empty)?;
+
+    if let Some(c) = name.chars().find(|c| !is_name_char(*c)) {
+        return Err(InvalidProjectName::Character(name.to_string(), c));
+    }
+
+    if !(first.is_alphabetic() || first == '_') {
+        return Err(InvalidProjectName::Start(name.to_string(), first));
+    }
+
+    if !name.contains(char::is_alphanumeric) {
+        return Err(InvalidProjectName::NoAlphanumeric(name.to_string()));
+    }
+
+    Ok(())
+}
+
+// Turns a directory name into a valid project name: characters which aren't allowed become
+// underscores, a name which doesn't start with a letter gets a leading underscore and a name
+// without letters or digits gets a `project` prefix
+pub fn sanitize(name: &str) -> String {
+    make_valid(name, is_name_char, char::is_alphabetic, char::is_alphanumeric)
+}
+
+// CMake target names are limited to ASCII letters, digits and `_.+-`, so other characters of the
+// project name become underscores. Names CMake reserves get a leading underscore
+pub fn target_name(project_name: &str) -> String {
+    let target = make_valid(
+        project_name,
+        is_target_char,
+        |c| c.is_ascii_alphabetic(),
+        |c| c.is_ascii_alphanumeric(),
+    );
+
+    if RESERVED_NAMES.contains(&target.as_str()) {
+        format!("_{}", target)
+    } else {
+        target
+    }
+}
+
+fn make_valid(
+    name: &str,
+    is_allowed: fn(char) -> bool,
+    is_letter: fn(char) -> bool,
+    is_alphanumeric: fn(char) -> bool,
+) -> String {
+    let sanitized: String = name.chars().map(|c| if is_allowed(c) { c } else { '_' }).collect();
+
+    if sanitized.is_empty() {
+        String::from("project")
+    } else if !sanitized.contains(is_alphanumeric) {
+        format!("project_{}", sanitized)
+    } else if sanitized.starts_with(|c: char| is_letter(c) || c == '_') {
+        sanitized
+    } else {
+        format!("_{}", sanitized)
+    }
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || "_.+-".contains(c)
+}
+
+fn is_target_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "_.+-".contains(c)
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum InvalidProjectName {
+    Empty,
+    Character(String, char),
+    Start(String, char),
+    NoAlphanumeric(String),
+}
+
+impl fmt::Display for InvalidProjectName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use InvalidProjectName::*;
+        match self {
+            Empty => write!(f, "project name is empty"),
+            Character(name, c) => write!(f, "`{}` contains `{}`", name, c),
+            Start(name, c) => write!(f, "`{}` starts with `{}`", name, c),
+            NoAlphanumeric(name) => write!(f, "`{}` has no letters or digits", name),
+        }
+    }
+}
+
+impl Error for InvalidProjectName {
+    fn description(&self) -> &str {
+        "Invalid project name"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validation() {
+        for name in &["demo", "my-lib", "lib.core", "gtk+", "_tool2", "test", "café", "日本", "Ωmega-2"] {
+            assert_eq!(validate(name), Ok(()), "{}", name);
+        }
+
+        assert_eq!(validate(""), Err(InvalidProjectName::Empty));
+        assert_eq!(validate("2048"), Err(InvalidProjectName::Start(String::from("2048"), '2')));
+        assert_eq!(validate("-foo"), Err(InvalidProjectName::Start(String::from("-foo"), '-')));
+        assert_eq!(validate("__"), Err(InvalidProjectName::NoAlphanumeric(String::from("__"))));
+
+        for name in &["my app", "a;b", "$HOME", "f(x)", "\"q\"", "a/b"] {
+            assert!(matches!(validate(name), Err(InvalidProjectName::Character(..))), "{}", name);
+        }
+    }
+
+    #[test]
+    fn sanitizing() {
+        assert_eq!(sanitize("demo"), "demo");
+        assert_eq!(sanitize("my app"), "my_app");
+        assert_eq!(sanitize("café-2.0"), "café-2.0");
+        assert_eq!(sanitize("2048"), "_2048");
+        assert_eq!(sanitize(".hidden"), "_.hidden");
+        assert_eq!(sanitize("a;b$(c)"), "a_b__c_");
+        assert_eq!(sanitize("!!"), "project___");
+        assert_eq!(sanitize(""), "project");
+
+        for name in &["日本", "3d engine", "()", "test", "-", "ä ö"] {
+            assert_eq!(validate(&sanitize(name)), Ok(()), "{}", name);
+        }
+    }
+
+    #[test]
+    fn target_names() {
+        assert_eq!(target_name("demo"), "demo");
+        assert_eq!(target_name("café"), "caf_");
+        assert_eq!(target_name("日本"), "project___");
+        assert_eq!(target_name("Ωmega"), "_mega");
+        assert_eq!(target_name("test"), "_test");
+        assert_eq!(target_name("gtk+"), "gtk+");
+
+        for name in &["日本", "café", "test", "__", "2048"] {
+            let target = target_name(name);
+            assert!(target.chars().all(is_target_char) && !RESERVED_NAMES.contains(&target.as_str()), "{}", name);
+        }
+    }
+}
@@ -0,0 +1,242 @@
+/// This module describes sanitizer builds and parses sanitizer reports
+use serde::Serialize;
+
+use std::{error::Error, fmt, str::FromStr};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Sanitizer {
+    Address,
+    Undefined,
+    Thread,
+    Memory,
+    Leak,
+}
+
+// Sanitizers which can't be linked into the same executable
+const INCOMPATIBLE: [(Sanitizer, Sanitizer); 5] = [
+    (Sanitizer::Address, Sanitizer::Thread),
+    (Sanitizer::Address, Sanitizer::Memory),
+    (Sanitizer::Thread, Sanitizer::Memory),
+    (Sanitizer::Thread, Sanitizer::Leak),
+    (Sanitizer::Memory, Sanitizer::Leak),
+];
+
+impl Sanitizer {
+    // Name accepted by -fsanitize=
+    pub fn name(self) -> &'static str {
+        match self {
+            Sanitizer::Address => "address",
+            Sanitizer::Undefined => "undefined",
+            Sanitizer::Thread => "thread",
+            Sanitizer::Memory => "memory",
+            Sanitizer::Leak => "leak",
+        }
+    }
+
+    // Runtime options used unless the user has already set the variable
+    fn default_options(self) -> (&'static str, &'static str) {
+        match self {
+            Sanitizer::Address => (
+                "ASAN_OPTIONS",
+                "detect_leaks=1:detect_stack_use_after_return=1:check_initialization_order=1:strict_string_checks=1",
+            ),
+            Sanitizer::Undefined => ("UBSAN_OPTIONS", "print_stacktrace=1:print_summary=1"),
+            Sanitizer::Thread => ("TSAN_OPTIONS", "second_deadlock_stack=1"),
+            Sanitizer::Memory => ("MSAN_OPTIONS", "poison_in_dtor=1"),
+            Sanitizer::Leak => ("LSAN_OPTIONS", "report_objects=1"),
+        }
+    }
+}
+
+impl FromStr for Sanitizer {
+    type Err = ParseSanitizerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "address" | "asan" => Ok(Sanitizer::Address),
+            "undefined" | "ubsan" => Ok(Sanitizer::Undefined),
+            "thread" | "tsan" => Ok(Sanitizer::Thread),
+            "memory" | "msan" => Ok(Sanitizer::Memory),
+            "leak" | "lsan" => Ok(Sanitizer::Leak),
+            other => Err(ParseSanitizerError::Unknown(other.to_string())),
+        }
+    }
+}
+
+// Set of sanitizers enabled for a build
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SanitizerSet(Vec<Sanitizer>);
+
+impl SanitizerSet {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn compile_flags(&self) -> String {
+        if self.is_empty() {
+            return String::new();
+        }
+
+        let mut flags = format!("{} -fno-omit-frame-pointer -g", self.link_flags());
+
+        if self.0.contains(&Sanitizer::Memory) {
+            flags.push_str(" -fsanitize-memory-track-origins");
+        }
+
+        flags
+    }
+
+    pub fn link_flags(&self) -> String {
+        if self.is_empty() {
+            return String::new();
+        }
+
+        let names: Vec<&str> = self.0.iter().map(|sanitizer| sanitizer.name()).collect();
+        format!("-fsanitize={}", names.join(","))
+    }
+
+    // Name of the build directory, so sanitized objects never mix with regular ones
+    pub fn build_dir_name(&self) -> String {
+        let names: Vec<&str> = self.0.iter().map(|sanitizer| sanitizer.name()).collect();
+        format!("sanitize-{}", names.join("-"))
+    }
+
+    pub fn runtime_options(&self) -> Vec<(&'static str, &'static str)> {
+        self.0.iter().map(|sanitizer| sanitizer.default_options()).collect()
+    }
+}
+
+impl fmt::Display for SanitizerSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let names: Vec<&str> = self.0.iter().map(|sanitizer| sanitizer.name()).collect();
+        write!(f, "{}", names.join(","))
+    }
+}
+
+impl FromStr for SanitizerSet {
+    type Err = ParseSanitizerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut sanitizers = s
+            .split(',')
+            .filter(|name| !name.trim().is_empty())
+            .map(str::parse)
+            .collect::<Result<Vec<Sanitizer>, _>>()?;
+
+        sanitizers.sort();
+        sanitizers.dedup();
+
+        for (first, second) in INCOMPATIBLE.iter() {
+            if sanitizers.contains(first) && sanitizers.contains(second) {
+                return Err(ParseSanitizerError::Incompatible(*first, *second));
+            }
+        }
+
+        Ok(SanitizerSet(sanitizers))
+    }
+}
+
+// Issue found by a sanitizer at runtime
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Report {
+    pub tool: String,
+    pub message: String,
+}
+
+// Recognizes report headlines in the stderr of a sanitized program. ASan, TSan, MSan and LSan
+// finish their reports with a SUMMARY line. UBSan prints a "runtime error" line for every issue
+pub fn parse_report(line: &str) -> Option<Report> {
+    if let Some(pos) = line.find(": runtime error: ") {
+        return Some(Report {
+            tool: String::from("UndefinedBehaviorSanitizer"),
+            message: format!("{} at {}", &line[pos + 17..], &line[..pos]),
+        });
+    }
+
+    let summary = line.trim().strip_prefix("SUMMARY: ")?;
+    let (tool, message) = summary.split_at(summary.find(": ")?);
+
+    if tool == "UndefinedBehaviorSanitizer" {
+        // Already reported by a runtime error line
+        return None;
+    }
+
+    Some(Report {
+        tool: tool.to_string(),
+        message: message[2..].to_string(),
+    })
+}
+
+#[derive(Debug)]
+pub enum ParseSanitizerError {
+    Unknown(String),
+    Incompatible(Sanitizer, Sanitizer),
+}
+
+impl fmt::Display for ParseSanitizerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseSanitizerError::Unknown(name) => write!(f, "unknown sanitizer `{}`", name),
+            ParseSanitizerError::Incompatible(first, second) => write!(
+                f,
+                "{} and {} sanitizers can't be used together",
+                first.name(),
+                second.name()
+            ),
+        }
+    }
+}
+
+impl Error for ParseSanitizerError {
+    fn description(&self) -> &str {
+        "Invalid sanitizer list"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_set() {
+        let set: SanitizerSet = "undefined,asan,address".parse().unwrap();
+        assert_eq!(set.to_string(), "address,undefined");
+        assert_eq!(set.build_dir_name(), "sanitize-address-undefined");
+        assert_eq!(set.link_flags(), "-fsanitize=address,undefined");
+        assert_eq!(set.compile_flags(), "-fsanitize=address,undefined -fno-omit-frame-pointer -g");
+
+        let empty: SanitizerSet = "".parse().unwrap();
+        assert!(empty.is_empty());
+        assert_eq!(empty.compile_flags(), "");
+
+        assert!("address,foo".parse::<SanitizerSet>().is_err());
+        assert!("thread,address".parse::<SanitizerSet>().is_err());
+        assert!("memory,leak".parse::<SanitizerSet>().is_err());
+        assert!("thread,undefined".parse::<SanitizerSet>().is_ok());
+    }
+
+    #[test]
+    fn reports() {
+        let asan = parse_report("SUMMARY: AddressSanitizer: heap-use-after-free /tmp/demo/main.cpp:7 in main");
+        assert_eq!(
+            asan,
+            Some(Report {
+                tool: String::from("AddressSanitizer"),
+                message: String::from("heap-use-after-free /tmp/demo/main.cpp:7 in main"),
+            })
+        );
+
+        let ubsan = parse_report("/tmp/demo/main.cpp:5:7: runtime error: signed integer overflow: 2147483647 + 1");
+        assert_eq!(
+            ubsan,
+            Some(Report {
+                tool: String::from("UndefinedBehaviorSanitizer"),
+                message: String::from("signed integer overflow: 2147483647 + 1 at /tmp/demo/main.cpp:5:7"),
+            })
+        );
+
+        assert_eq!(parse_report("SUMMARY: UndefinedBehaviorSanitizer: undefined-behavior main.cpp:5:7"), None);
+        assert_eq!(parse_report("==123==ERROR: AddressSanitizer: heap-use-after-free"), None);
+        assert_eq!(parse_report("Hello world"), None);
+    }
+}
@@ -0,0 +1,42 @@
+/// This module gives tests directories of their own, which are removed when the test ends
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+// Tells apart directories of tests running in the same process, even with the same name
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+// An empty directory in the system temp directory. It is removed on drop, so failed assertions
+// don't leave it behind
+pub struct ScratchDir {
+    path: PathBuf,
+}
+
+impl ScratchDir {
+    pub fn new(name: &str) -> Self {
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        let path = std::env::temp_dir().join(format!("cbake-{}-{}-{}", name, std::process::id(), id));
+
+        // Left over by a killed test run of a process with the same pid
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+
+        ScratchDir { path }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn join<P: AsRef<Path>>(&self, path: P) -> PathBuf {
+        self.path.join(path)
+    }
+}
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
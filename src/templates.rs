@@ -0,0 +1,205 @@
+/// This module generates contents of new source files
+use std::{error::Error, fmt};
+
+// A class with a header and a source file
+pub struct ClassTemplate {
+    name: String,
+    namespaces: Vec<String>,
+    pragma_once: bool,
+}
+
+impl ClassTemplate {
+    // The namespace may be nested like app::detail
+    pub fn new(name: &str, namespace: Option<&str>) -> Result<Self, InvalidIdentifier> {
+        let namespaces: Vec<String> = match namespace {
+            Some(namespace) => namespace.split("::").map(String::from).collect(),
+            None => Vec::new(),
+        };
+
+        for identifier in namespaces.iter().chain(std::iter::once(&name.to_string())) {
+            if !is_identifier(identifier) {
+                return Err(InvalidIdentifier(identifier.clone()));
+            }
+        }
+
+        Ok(ClassTemplate {
+            name: name.to_string(),
+            namespaces,
+            pragma_once: false,
+        })
+    }
+
+    // Include guards are used by default since #pragma once is not standard C++
+    pub fn pragma_once(mut self, enable: bool) -> Self {
+        self.pragma_once = enable;
+        self
+    }
+
+    pub fn header(&self, header_file: &str) -> String {
+        let body = self.in_namespaces(&format!(
+            "class {name} {{\npublic:\n    {name}();\n    ~{name}();\n}};\n",
+            name = self.name
+        ));
+
+        if self.pragma_once {
+            return format!("#pragma once\n\n{}", body);
+        }
+
+        let guard = self.include_guard(header_file);
+        format!("#ifndef {guard}\n#define {guard}\n\n{}\n#endif // {guard}\n", body, guard = guard)
+    }
+
+    pub fn source(&self, header_include: &str) -> String {
+        let body = self.in_namespaces(&format!(
+            "{name}::{name}() {{\n}}\n\n{name}::~{name}() {{\n}}\n",
+            name = self.name
+        ));
+
+        format!("#include \"{}\"\n\n{}", header_include, body)
+    }
+
+    fn in_namespaces(&self, body: &str) -> String {
+        if self.namespaces.is_empty() {
+            return body.to_string();
+        }
+
+        let opening: String = self
+            .namespaces
+            .iter()
+            .map(|namespace| format!("namespace {} {{\n", namespace))
+            .collect();
+        let closing: String = self
+            .namespaces
+            .iter()
+            .rev()
+            .map(|namespace| format!("}} // namespace {}\n", namespace))
+            .collect();
+
+        format!("{}\n{}\n{}", opening, body, closing)
+    }
+
+    // APP_DETAIL_FOO_H for app::detail and Foo.h
+    fn include_guard(&self, header_file: &str) -> String {
+        let file_name = header_file.rsplit(['/', '\\']).next().unwrap_or(header_file);
+
+        let mut parts = self.namespaces.clone();
+        parts.push(file_name.to_string());
+
+        parts
+            .join("_")
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+            .collect()
+    }
+}
+
+// Header of a new library with a function in the namespace named after the library
+pub fn library_header(namespace: &str, header_file: &str) -> String {
+    let guard: String = header_file
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+        .collect();
+
+    format!(
+        "#ifndef {guard}\n#define {guard}\n\nnamespace {namespace} {{\n\nint answer();\n\n}} // namespace {namespace}\n\n#endif // {guard}\n",
+        guard = guard,
+        namespace = namespace
+    )
+}
+
+pub fn library_source(namespace: &str, header_include: &str) -> String {
+    format!(
+        "#include \"{}\"\n\nnamespace {namespace} {{\n\nint answer() {{\n    return 42;\n}}\n\n}} // namespace {namespace}\n",
+        header_include,
+        namespace = namespace
+    )
+}
+
+// Turns a project name like my-lib into an identifier usable as a namespace
+pub fn to_identifier(name: &str) -> String {
+    let identifier: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+
+    if identifier.starts_with(|c: char| c.is_ascii_digit()) || identifier.is_empty() {
+        format!("_{}", identifier)
+    } else {
+        identifier
+    }
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+
+    match chars.next() {
+        Some(first) if first.is_ascii_alphabetic() || first == '_' => {
+            chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        }
+        _ => false,
+    }
+}
+
+#[derive(Debug)]
+pub struct InvalidIdentifier(String);
+
+impl fmt::Display for InvalidIdentifier {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "`{}` is not a C++ identifier", self.0)
+    }
+}
+
+impl Error for InvalidIdentifier {
+    fn description(&self) -> &str {
+        "Invalid C++ identifier"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn class_files() {
+        let class = ClassTemplate::new("Foo", None).unwrap();
+
+        assert_eq!(
+            class.header("Foo.h"),
+            "#ifndef FOO_H\n#define FOO_H\n\nclass Foo {\npublic:\n    Foo();\n    ~Foo();\n};\n\n#endif // FOO_H\n"
+        );
+        assert_eq!(
+            class.source("Foo.h"),
+            "#include \"Foo.h\"\n\nFoo::Foo() {\n}\n\nFoo::~Foo() {\n}\n"
+        );
+
+        let nested = ClassTemplate::new("Parser", Some("app::detail")).unwrap().pragma_once(true);
+        let header = nested.header("include/Parser.hpp");
+
+        assert!(header.starts_with("#pragma once\n\nnamespace app {\nnamespace detail {\n\nclass Parser {"));
+        assert!(header.ends_with("};\n\n} // namespace detail\n} // namespace app\n"));
+        assert_eq!(
+            ClassTemplate::new("Parser", Some("app::detail")).unwrap().include_guard("include/Parser.hpp"),
+            "APP_DETAIL_PARSER_HPP"
+        );
+    }
+
+    #[test]
+    fn library_files() {
+        let header = library_header("my_lib", "my-lib.h");
+        assert!(header.starts_with("#ifndef MY_LIB_H\n#define MY_LIB_H\n\nnamespace my_lib {\n"));
+        assert!(library_source("my_lib", "my-lib.h").starts_with("#include \"my-lib.h\"\n"));
+
+        assert_eq!(to_identifier("my-lib"), "my_lib");
+        assert_eq!(to_identifier("3d.engine"), "_3d_engine");
+        assert!(is_identifier(&to_identifier("a b")));
+    }
+
+    #[test]
+    fn identifiers() {
+        assert!(ClassTemplate::new("_Foo2", Some("a::b_c")).is_ok());
+        assert!(ClassTemplate::new("2Foo", None).is_err());
+        assert!(ClassTemplate::new("Foo-bar", None).is_err());
+        assert!(ClassTemplate::new("Foo", Some("a::")).is_err());
+        assert!(ClassTemplate::new("", None).is_err());
+    }
+}
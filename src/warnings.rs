@@ -0,0 +1,122 @@
+/// This module translates warning presets into compiler flags
+use serde::Deserialize;
+
+use std::{error::Error, fmt, str::FromStr};
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WarningLevel {
+    // Warnings are turned off
+    None,
+    // Whatever the compiler warns about by default
+    #[default]
+    Default,
+    Strict,
+    Pedantic,
+}
+
+impl FromStr for WarningLevel {
+    type Err = ParseWarningLevelError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "none" => Ok(WarningLevel::None),
+            "default" => Ok(WarningLevel::Default),
+            "strict" => Ok(WarningLevel::Strict),
+            "pedantic" => Ok(WarningLevel::Pedantic),
+            other => Err(ParseWarningLevelError(other.to_string())),
+        }
+    }
+}
+
+// Warning level along with warnings-as-errors, the same for the whole project
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct WarningPolicy {
+    pub level: WarningLevel,
+    pub deny: bool,
+}
+
+impl WarningPolicy {
+    // The compiler defaults need no flags
+    pub fn is_empty(&self) -> bool {
+        self.level == WarningLevel::Default && !self.deny
+    }
+
+    // Flags understood by GCC and Clang
+    pub fn gnu_flags(&self) -> Vec<&'static str> {
+        let mut flags = match self.level {
+            WarningLevel::None => vec!["-w"],
+            WarningLevel::Default => vec![],
+            WarningLevel::Strict => vec!["-Wall", "-Wextra"],
+            WarningLevel::Pedantic => vec!["-Wall", "-Wextra", "-Wpedantic"],
+        };
+
+        if self.deny {
+            flags.push("-Werror");
+        }
+
+        flags
+    }
+
+    // /Wall of MSVC warns about the standard headers too, so /W4 is the strictest usable level
+    pub fn msvc_flags(&self) -> Vec<&'static str> {
+        let mut flags = match self.level {
+            WarningLevel::None => vec!["/W0"],
+            WarningLevel::Default => vec![],
+            WarningLevel::Strict => vec!["/W4"],
+            WarningLevel::Pedantic => vec!["/W4", "/permissive-"],
+        };
+
+        if self.deny {
+            flags.push("/WX");
+        }
+
+        flags
+    }
+}
+
+#[derive(Debug)]
+pub struct ParseWarningLevelError(String);
+
+impl fmt::Display for ParseWarningLevelError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unknown warning level `{}`", self.0)
+    }
+}
+
+impl Error for ParseWarningLevelError {
+    fn description(&self) -> &str {
+        "Unknown warning level"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags() {
+        let strict = WarningPolicy {
+            level: "strict".parse().unwrap(),
+            deny: true,
+        };
+        assert_eq!(strict.gnu_flags(), vec!["-Wall", "-Wextra", "-Werror"]);
+        assert_eq!(strict.msvc_flags(), vec!["/W4", "/WX"]);
+
+        let pedantic = WarningPolicy {
+            level: WarningLevel::Pedantic,
+            deny: false,
+        };
+        assert_eq!(pedantic.gnu_flags(), vec!["-Wall", "-Wextra", "-Wpedantic"]);
+
+        let deny_only = WarningPolicy {
+            deny: true,
+            ..WarningPolicy::default()
+        };
+        assert!(!deny_only.is_empty());
+        assert_eq!(deny_only.gnu_flags(), vec!["-Werror"]);
+
+        assert!(WarningPolicy::default().is_empty());
+        assert!("loud".parse::<WarningLevel>().is_err());
+    }
+}
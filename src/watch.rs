@@ -0,0 +1,128 @@
+/// This module polls project files for changes
+use crate::manifest;
+use crate::project_layout::ProjectLayout;
+
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, SystemTime},
+};
+
+// Polling works on every platform and file system, including network shares and containers
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+// Editors often save a file in several steps, changes are reported once files stop changing
+const DEBOUNCE_INTERVAL: Duration = Duration::from_millis(200);
+
+// Modification times of the watched files
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Snapshot(BTreeMap<PathBuf, Option<SystemTime>>);
+
+impl Snapshot {
+    // Sources are collected anew every time, so added and removed files are noticed too
+    pub fn take(layout: &dyn ProjectLayout) -> io::Result<Self> {
+        let project_root = layout.get_project_path();
+
+        let mut files: Vec<PathBuf> = layout
+            .collect_sources()?
+            .iter()
+            .map(|source| project_root.join(source))
+            .collect();
+
+        files.push(project_root.join("CMakeLists.txt"));
+        files.push(project_root.join(manifest::MANIFEST_FILE));
+
+        Ok(Snapshot(
+            files
+                .into_iter()
+                .map(|file| {
+                    let modified = fs::metadata(&file).and_then(|metadata| metadata.modified()).ok();
+                    (file, modified)
+                })
+                .collect(),
+        ))
+    }
+
+    pub fn file_count(&self) -> usize {
+        self.0.values().filter(|modified| modified.is_some()).count()
+    }
+
+    // Files which were added, removed or modified since the older snapshot
+    pub fn changes(&self, older: &Snapshot) -> Vec<PathBuf> {
+        let mut changed: Vec<PathBuf> = self
+            .0
+            .iter()
+            .filter(|(file, modified)| older.0.get(*file) != Some(modified))
+            .map(|(file, _)| file.clone())
+            .collect();
+
+        changed.extend(older.0.keys().filter(|file| !self.0.contains_key(*file)).cloned());
+        changed.sort();
+        changed
+    }
+}
+
+// Blocks until project files change and returns the changed ones. on_poll is called between polls,
+// e.g. to notice that a running program has exited
+pub fn wait_for_changes(
+    layout: &dyn ProjectLayout,
+    snapshot: &mut Snapshot,
+    on_poll: &mut dyn FnMut(),
+) -> io::Result<Vec<PathBuf>> {
+    loop {
+        thread::sleep(POLL_INTERVAL);
+        on_poll();
+
+        let mut current = Snapshot::take(layout)?;
+        if current == *snapshot {
+            continue;
+        }
+
+        loop {
+            thread::sleep(DEBOUNCE_INTERVAL);
+
+            let next = Snapshot::take(layout)?;
+            if next == current {
+                break;
+            }
+            current = next;
+        }
+
+        let changed = current.changes(snapshot);
+        *snapshot = current;
+
+        if !changed.is_empty() {
+            return Ok(changed);
+        }
+    }
+}
+
+// Paths are shown relative to the project root to keep messages short
+pub fn display_path(file: &Path, project_root: &Path) -> String {
+    file.strip_prefix(project_root).unwrap_or(file).display().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn changes() {
+        let time = |secs| Some(SystemTime::UNIX_EPOCH + Duration::from_secs(secs));
+        let snapshot = |files: &[(&str, Option<SystemTime>)]| {
+            Snapshot(files.iter().map(|(file, modified)| (PathBuf::from(file), *modified)).collect())
+        };
+
+        let older = snapshot(&[("main.cpp", time(1)), ("util.cpp", time(1)), ("cbake.toml", None)]);
+        let newer = snapshot(&[("main.cpp", time(2)), ("util.h", time(2)), ("cbake.toml", None)]);
+
+        assert_eq!(older.file_count(), 2);
+        assert!(older.changes(&older).is_empty());
+        assert_eq!(
+            newer.changes(&older),
+            vec![PathBuf::from("main.cpp"), PathBuf::from("util.cpp"), PathBuf::from("util.h")]
+        );
+    }
+}
@@ -0,0 +1,406 @@
+// End-to-end tests of the cbake binary. A shell script standing in for cmake is put on PATH, it records
+// its arguments and "builds" an executable which prints a greeting. Tests against the real cmake are
+// ignored, `cargo test -- --ignored` runs them
+#![cfg(unix)]
+
+use std::{
+    env, fs,
+    os::unix::fs::PermissionsExt,
+    path::{Path, PathBuf},
+    process::{Command, Output},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+const FAKE_CMAKE: &str = r#"#!/bin/sh
+echo "$*" >> "$FAKE_CMAKE_LOG"
+
+case "$1" in
+    --version)
+        echo "cmake version 3.16.3"
+        exit 0
+        ;;
+    --install)
+        exit 0
+        ;;
+    --build)
+        . "$2/fake-cache"
+        mkdir -p "$2/$output_dir"
+        printf '#!/bin/sh\necho "Hello from %s"\nexit ${FAKE_EXIT_STATUS:-0}\n' "$project" > "$2/$output_dir/$project"
+        chmod +x "$2/$output_dir/$project"
+        exit 0
+        ;;
+esac
+
+output_dir=.
+for arg in "$@"; do
+    case "$arg" in
+        -B*) build_dir="${arg#-B}" ;;
+        -H*) source_dir="${arg#-H}" ;;
+        -DCMAKE_RUNTIME_OUTPUT_DIRECTORY=*) output_dir="${arg#*=}" ;;
+    esac
+done
+
+mkdir -p "$build_dir"
+project=$(sed -n 's/^set(CBAKE_TARGET \([^ )]*\)).*/\1/p' "$source_dir/CMakeLists.txt" | head -n 1)
+printf 'output_dir=%s\nproject=%s\n' "$output_dir" "$project" > "$build_dir/fake-cache"
+echo "[]" > "$build_dir/compile_commands.json"
+"#;
+
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+// Temporary directory with the fake cmake, removed when the test ends
+struct Sandbox {
+    root: PathBuf,
+}
+
+impl Sandbox {
+    fn new() -> Self {
+        let id = NEXT_ID.fetch_add(1, Ordering::SeqCst);
+        let root = env::temp_dir().join(format!("cbake-cli-{}-{}", std::process::id(), id));
+
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("bin")).unwrap();
+
+        let cmake = root.join("bin/cmake");
+        fs::write(&cmake, FAKE_CMAKE).unwrap();
+        fs::set_permissions(&cmake, fs::Permissions::from_mode(0o755)).unwrap();
+
+        Sandbox { root }
+    }
+
+    // cbake started in `dir` relative to the sandbox root with the fake cmake first on PATH
+    fn command(&self, dir: &str, args: &[&str]) -> Command {
+        let path = format!("{}:{}", self.root.join("bin").display(), env::var("PATH").unwrap_or_default());
+
+        let mut command = Command::new(env!("CARGO_BIN_EXE_cbake"));
+        command
+            .args(args)
+            .current_dir(self.root.join(dir))
+            .env("PATH", path)
+            .env("FAKE_CMAKE_LOG", self.log_path())
+            .env_remove("CBAKE_CMAKE");
+        command
+    }
+
+    fn cbake(&self, dir: &str, args: &[&str]) -> Output {
+        self.command(dir, args).output().unwrap()
+    }
+
+    fn log_path(&self) -> PathBuf {
+        self.root.join("cmake.log")
+    }
+
+    // Command lines the fake cmake was called with
+    fn cmake_calls(&self) -> Vec<String> {
+        fs::read_to_string(self.log_path())
+            .unwrap_or_default()
+            .lines()
+            .map(String::from)
+            .collect()
+    }
+
+    fn path(&self, relative: &str) -> PathBuf {
+        self.root.join(relative)
+    }
+}
+
+impl Drop for Sandbox {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+fn assert_success(output: &Output) {
+    assert!(
+        output.status.success(),
+        "status: {:?}\nstdout:\n{}\nstderr:\n{}",
+        output.status,
+        stdout(output),
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+fn json_reasons(output: &Output) -> Vec<String> {
+    stdout(output)
+        .lines()
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .filter_map(|message| message["reason"].as_str().map(String::from))
+        .collect()
+}
+
+#[test]
+fn new_project() {
+    let sandbox = Sandbox::new();
+
+    assert_success(&sandbox.cbake(".", &["new", "demo"]));
+
+    assert!(sandbox.path("demo/main.cpp").is_file());
+    assert!(fs::read_to_string(sandbox.path("demo/cbake.toml")).unwrap().contains("name = \"demo\""));
+
+    let cmake_lists = fs::read_to_string(sandbox.path("demo/CMakeLists.txt")).unwrap();
+    assert!(cmake_lists.starts_with("cmake_minimum_required(VERSION 3.1)\n"));
+    assert!(cmake_lists.contains("set(CBAKE_TARGET demo)\n\nadd_executable(${CBAKE_TARGET}\n    main.cpp\n)"));
+
+    let calls = sandbox.cmake_calls();
+    assert_eq!(calls[0], "--version");
+    assert!(calls[1].ends_with("-Bdemo/build -Hdemo"));
+
+    // The directory is already there
+    assert_eq!(sandbox.cbake(".", &["new", "demo"]).status.code(), Some(103));
+
+    // CMakeLists.txt of older versions gets the target variable on the next build
+    let old_cmake_lists = cmake_lists
+        .replace("set(CBAKE_TARGET demo)\n", "")
+        .replace("${CBAKE_TARGET}", "${PROJECT_NAME}");
+    fs::write(sandbox.path("demo/CMakeLists.txt"), old_cmake_lists).unwrap();
+    assert_success(&sandbox.cbake("demo", &["build"]));
+
+    let cmake_lists = fs::read_to_string(sandbox.path("demo/CMakeLists.txt")).unwrap();
+    assert!(cmake_lists.contains("set(CBAKE_TARGET demo)\nadd_executable(${CBAKE_TARGET}\n    main.cpp\n)"));
+    assert_eq!(cmake_lists.matches("set(CBAKE_TARGET").count(), 1);
+    assert!(sandbox.path("demo/build/Debug/demo").is_file());
+}
+
+#[test]
+fn project_names() {
+    let sandbox = Sandbox::new();
+
+    // The directory name is made into a valid target name
+    assert_success(&sandbox.cbake(".", &["new", "my app"]));
+    assert!(fs::read_to_string(sandbox.path("my app/CMakeLists.txt")).unwrap().contains("project(my_app "));
+
+    fs::write(sandbox.path("my app/extra file.cpp"), "").unwrap();
+    assert_success(&sandbox.cbake("my app", &["build"]));
+    assert!(sandbox.path("my app/build/Debug/my_app").is_file());
+
+    let cmake_lists = fs::read_to_string(sandbox.path("my app/CMakeLists.txt")).unwrap();
+    assert!(cmake_lists.contains("    \"extra file.cpp\"\n    main.cpp\n)"));
+
+    // The source list with quoted names is rewritten as a whole on the next build
+    fs::write(sandbox.path("my app/${x};(y).cpp"), "").unwrap();
+    assert_success(&sandbox.cbake("my app", &["build"]));
+
+    let cmake_lists = fs::read_to_string(sandbox.path("my app/CMakeLists.txt")).unwrap();
+    assert!(cmake_lists.contains("(${CBAKE_TARGET}\n    \"\\${x}\\;(y).cpp\"\n    \"extra file.cpp\"\n    main.cpp\n)\n"));
+    assert_eq!(cmake_lists.matches("main.cpp").count(), 1);
+
+    assert_success(&sandbox.cbake(".", &["new", "2048", "--name", "game"]));
+    assert!(fs::read_to_string(sandbox.path("2048/cbake.toml")).unwrap().contains("name = \"game\""));
+
+    // Unicode names are kept for the project, the target gets an ASCII name
+    assert_success(&sandbox.cbake(".", &["new", "café"]));
+    assert!(fs::read_to_string(sandbox.path("café/cbake.toml")).unwrap().contains("name = \"café\""));
+
+    let cmake_lists = fs::read_to_string(sandbox.path("café/CMakeLists.txt")).unwrap();
+    assert!(cmake_lists.contains("project(café ") && cmake_lists.contains("set(CBAKE_TARGET caf_)\n"));
+
+    assert_success(&sandbox.cbake("café", &["build"]));
+    assert!(sandbox.path("café/build/Debug/caf_").is_file());
+
+    assert_success(&sandbox.cbake(".", &["new", "日本"]));
+    assert!(fs::read_to_string(sandbox.path("日本/CMakeLists.txt")).unwrap().contains("project(日本 "));
+
+    // Targets cmake reserves are renamed
+    assert_success(&sandbox.cbake(".", &["new", "test"]));
+    assert!(fs::read_to_string(sandbox.path("test/CMakeLists.txt")).unwrap().contains("set(CBAKE_TARGET _test)\n"));
+
+    // Names cmake would choke on are rejected before anything is created
+    assert_eq!(sandbox.cbake(".", &["new", "tool", "--name", "a;b"]).status.code(), Some(119));
+    assert!(!sandbox.path("tool").exists());
+}
+
+#[test]
+fn build_profiles() {
+    let sandbox = Sandbox::new();
+    assert_success(&sandbox.cbake(".", &["new", "demo"]));
+
+    assert_success(&sandbox.cbake("demo", &["build"]));
+    assert!(sandbox.path("demo/build/Debug/demo").is_file());
+
+    assert_success(&sandbox.cbake("demo", &["build", "--release"]));
+    assert!(sandbox.path("demo/build/Release/demo").is_file());
+
+    let calls = sandbox.cmake_calls();
+    let configures: Vec<&String> = calls.iter().filter(|call| call.contains("-DCMAKE_BUILD_TYPE=")).collect();
+
+    assert_eq!(configures.len(), 2);
+    assert!(configures[0].contains("-DCMAKE_BUILD_TYPE=Debug") && configures[0].contains("-DCBAKE_PROFILE=debug"));
+    assert!(configures[1].contains("-DCMAKE_BUILD_TYPE=Release"));
+    assert_eq!(calls.iter().filter(|call| call.starts_with("--build")).count(), 2);
+
+    // Flags of the user in the cmake cache are never overwritten
+    assert!(calls.iter().all(|call| !call.contains("CMAKE_C_FLAGS") && !call.contains("CMAKE_CXX_FLAGS")));
+    assert!(calls.iter().all(|call| !call.contains("CBAKE_COMPILE_FLAGS")));
+
+    // Sources added later are picked up by the next build
+    fs::write(sandbox.path("demo/util.cpp"), "").unwrap();
+    assert_success(&sandbox.cbake("demo", &["build"]));
+
+    let cmake_lists = fs::read_to_string(sandbox.path("demo/CMakeLists.txt")).unwrap();
+    assert!(cmake_lists.contains("    main.cpp\n    util.cpp\n"));
+    assert!(!cmake_lists.contains("CBAKE_COMPILE_FLAGS"));
+
+    // Sanitizer flags go to their own build directory and are applied by the settings section
+    assert_success(&sandbox.cbake("demo", &["build", "--sanitize", "address"]));
+    let calls = sandbox.cmake_calls();
+    let configure = calls.iter().rev().find(|call| call.contains("-DCMAKE_BUILD_TYPE=")).unwrap();
+    assert!(configure.contains("-DCBAKE_COMPILE_FLAGS=-fsanitize=address"));
+    assert!(!configure.contains("CMAKE_CXX_FLAGS"));
+
+    let cmake_lists = fs::read_to_string(sandbox.path("demo/CMakeLists.txt")).unwrap();
+    assert!(cmake_lists.contains("if(CBAKE_COMPILE_FLAGS)\n"));
+
+    // The settings section is written for the generator of CMakeLists.txt, only `cbake new` asked cmake for its version
+    assert_eq!(calls.iter().filter(|call| *call == "--version").count(), 1);
+}
+
+#[test]
+fn broken_profiles() {
+    let sandbox = Sandbox::new();
+    assert_success(&sandbox.cbake(".", &["new", "demo"]));
+
+    let manifest = fs::read_to_string(sandbox.path("demo/cbake.toml")).unwrap();
+    fs::write(
+        sandbox.path("demo/cbake.toml"),
+        format!("{}\n[profile.broken]\ninherits = \"missing\"\n", manifest),
+    )
+    .unwrap();
+
+    // Only the profile being built has to resolve
+    let output = sandbox.cbake("demo", &["build"]);
+    assert_success(&output);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("profile `broken`"));
+
+    assert_eq!(sandbox.cbake("demo", &["build", "--profile", "broken"]).status.code(), Some(110));
+}
+
+#[test]
+fn project_root_lookup() {
+    let sandbox = Sandbox::new();
+    assert_success(&sandbox.cbake(".", &["new", "demo"]));
+    fs::create_dir_all(sandbox.path("demo/src/detail")).unwrap();
+
+    // From a subdirectory of the project
+    assert_success(&sandbox.cbake("demo/src/detail", &["build"]));
+    assert!(sandbox.path("demo/build/Debug/demo").is_file());
+    assert!(!sandbox.path("demo/src/detail/build").exists());
+
+    // From outside of it
+    assert_success(&sandbox.cbake(".", &["-C", "demo/src", "build", "--release"]));
+    assert!(sandbox.path("demo/build/Release/demo").is_file());
+
+    assert_success(&sandbox.cbake(".", &["--manifest-path", "demo/cbake.toml", "clean"]));
+    assert!(!sandbox.path("demo/build").exists());
+
+    // The manifest path is taken as is
+    let output = sandbox.cbake(".", &["--manifest-path", "demo/src/cbake.toml", "build"]);
+    assert_eq!(output.status.code(), Some(107));
+
+    assert_success(&sandbox.cbake("demo", &["-C", "..", "new", "other"]));
+    assert!(sandbox.path("other/CMakeLists.txt").is_file());
+}
+
+#[test]
+fn run() {
+    let sandbox = Sandbox::new();
+    assert_success(&sandbox.cbake(".", &["new", "demo"]));
+
+    let output = sandbox.cbake("demo", &["--message-format", "json", "run"]);
+    assert_success(&output);
+    assert!(stdout(&output).contains("Hello from demo\n"));
+
+    // The command finishes like any other, with a single final message
+    let reasons = json_reasons(&output);
+    assert_eq!(reasons.iter().filter(|reason| *reason == "finished").count(), 1);
+    assert_eq!(reasons.last().map(String::as_str), Some("finished"));
+}
+
+#[test]
+fn run_failing_program() {
+    let sandbox = Sandbox::new();
+    assert_success(&sandbox.cbake(".", &["new", "demo"]));
+
+    let output = sandbox.command("demo", &["run"]).env("FAKE_EXIT_STATUS", "3").output().unwrap();
+
+    assert!(stdout(&output).contains("Hello from demo"));
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn clean() {
+    let sandbox = Sandbox::new();
+    assert_success(&sandbox.cbake(".", &["new", "demo"]));
+    assert_success(&sandbox.cbake("demo", &["build"]));
+    assert!(sandbox.path("demo/compile_commands.json").symlink_metadata().is_ok());
+
+    assert_success(&sandbox.cbake("demo", &["clean"]));
+    assert!(!sandbox.path("demo/build").exists());
+    assert!(sandbox.path("demo/compile_commands.json").symlink_metadata().is_err());
+    assert!(sandbox.path("demo/main.cpp").is_file());
+
+    // Nothing to clean outside of a project
+    assert_eq!(sandbox.cbake(".", &["clean"]).status.code(), Some(107));
+}
+
+#[test]
+fn install_prefix() {
+    let sandbox = Sandbox::new();
+    assert_success(&sandbox.cbake(".", &["new", "demo"]));
+
+    // Relative prefixes are resolved against the working directory, whatever -C says
+    let output = sandbox.cbake(".", &["-C", "demo", "install", "--prefix", "out"]);
+    assert_success(&output);
+    // The build before installing doesn't report a result of its own
+    assert_eq!(stdout(&output).matches("Success").count(), 1);
+
+    let calls = sandbox.cmake_calls();
+    let install = calls.iter().find(|call| call.starts_with("--install")).unwrap();
+    assert!(install.ends_with(&format!("--prefix {}", sandbox.path("out").display())), "{}", install);
+}
+
+#[test]
+fn init_not_implemented() {
+    let sandbox = Sandbox::new();
+
+    let output = sandbox.cbake(".", &["init"]);
+    assert_eq!(output.status.code(), Some(127));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("E0027"));
+}
+
+#[test]
+fn missing_cmake() {
+    let sandbox = Sandbox::new();
+
+    let output = sandbox.cbake(".", &["--cmake", "/nonexistent/cmake", "new", "demo"]);
+    assert_eq!(output.status.code(), Some(101));
+    assert!(!sandbox.path("demo").exists());
+}
+
+// Builds and runs a project with the cmake and the compiler installed on the machine
+#[test]
+#[ignore = "needs cmake and a C++ compiler, run with `cargo test -- --ignored`"]
+fn real_cmake() {
+    let root = env::temp_dir().join(format!("cbake-cli-real-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(&root).unwrap();
+
+    let cbake = |dir: &Path, args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_cbake"))
+            .args(args)
+            .current_dir(dir)
+            .env_remove("CBAKE_CMAKE")
+            .output()
+            .unwrap()
+    };
+
+    assert_success(&cbake(&root, &["new", "demo"]));
+    let output = cbake(&root.join("demo"), &["run"]);
+    assert_success(&output);
+    assert!(stdout(&output).contains("Hello world"));
+
+    let _ = fs::remove_dir_all(&root);
+}